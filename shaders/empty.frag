@@ -4,6 +4,14 @@ layout (location = 0) out vec4 out_color;
 layout (location = 0) in vec2 uv;   
 layout (binding  = 0) uniform UniformParameters {
     float time;
+    float fft;
+    float time_delta;
+    float frame;
+    vec2 resolution;
+    float aspect;
+    float fft_length;
+    vec4 mouse;
+    vec4 date;
 } u;
 layout (binding  = 1) readonly buffer fftBuffer{
     float v[];
@@ -4,6 +4,12 @@ layout (location = 0) out vec4 out_color;
 layout (location = 0) in vec2 uv;   
 layout (binding  = 0) uniform UniformParameters {
     float time;
+    float fft;
+    float time_delta;
+    float frame;
+    vec2 resolution;
+    float aspect;
+    float fft_length;
 } u;
 layout (binding  = 1) readonly buffer fftBuffer{
     float v[];
@@ -23,7 +29,7 @@ layout (binding  = 2) readonly buffer SliderParameters{
 
 
 void main(){
-    int index = int(min(uv.x * 1920.0, 1919.0));
+    int index = int(min(uv.x * u.fft_length, u.fft_length - 1.0));
     if (uv.y <= fft.v[index]) {
         out_color = vec4(0.0, 1.0, 0.5, 1.0);
     } else {
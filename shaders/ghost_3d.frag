@@ -4,6 +4,18 @@ layout (location = 0) out vec4 out_color;
 layout (location = 0) in vec2 uv;   
 layout (binding  = 0) uniform UniformParameters {
     float time;
+    float fft;
+    float time_delta;
+    float frame;
+    vec2 resolution;
+    float aspect;
+    float fft_length;
+    vec4 mouse;
+    vec4 date;
+    vec3 camera_position;
+    float camera_fov;
+    vec3 camera_forward;
+    vec3 camera_up;
 } u;
 layout (binding  = 1) readonly buffer fftBuffer{
     float v[];
@@ -18,7 +30,6 @@ layout (binding  = 2) readonly buffer SliderParameters{
 
 #define PI 3.1415926538
 #define TAU 6.2831853071
-#define FOV 60.0
 
 struct SceneSample {
     float closest_distance;
@@ -148,7 +159,7 @@ vec3 normal( in vec3 p ) // for function f(p)
                            scene_f(p+h.yyx) - scene_f(p-h.yyx) ) );
 }
 float fov_factor() {
-    return tan(FOV / 2.0 * PI / 180.0);
+    return tan(u.camera_fov / 2.0 * PI / 180.0);
 }
 
 
@@ -196,11 +207,11 @@ vec4 render(vec3 eye, vec3 ray) {
 }
 
 void main(){
-    float fov = fov_factor();
-    vec2 pixel_position = ((uv - 0.5) * vec2(1.92, 1.2)) / 1.2;
-    vec3 ray_direction = normalize(vec3(pixel_position, 1.0)); 
+    vec2 pixel_position = (uv - 0.5) * vec2(u.aspect, 1.0) * 2.0 * fov_factor();
+    vec3 camera_right = cross(u.camera_up, u.camera_forward);
+    vec3 ray_direction = normalize(u.camera_forward + pixel_position.x * camera_right + pixel_position.y * u.camera_up);
 
-    out_color = render(vec3(0.0), ray_direction);
+    out_color = render(u.camera_position, ray_direction);
     // out_color = vec4(pixel_position, 0.0, 1.0);
     // out_color = vec4(sin(sdFbm(vec3(uv * 40.0, 0.0), 7.0)), 0.0, 0.0, 1.0);
 } 
@@ -0,0 +1,29 @@
+// Inputs and output of every fragment shader, see "Required shader inputs" in the README.
+// Only the resources a shader uses are bound.
+layout (location = 0) out vec4 out_color;
+
+layout (location = 0) in vec2 uv;
+layout (binding = 0) uniform UniformParameters {
+    float time;
+    float fft;
+    float time_delta;
+    float frame;
+    vec2 resolution;
+    float aspect;
+    float fft_length;
+    vec4 mouse;
+    vec4 date;
+    vec3 camera_position;
+    float camera_fov;
+    vec3 camera_forward;
+    vec3 camera_up;
+} u;
+layout (binding = 1) readonly buffer fftBuffer {
+    float v[];
+} fft;
+layout (binding = 2) readonly buffer SliderParameters {
+    float v[];
+} sliders;
+layout (binding = 3) readonly buffer eyeBuffer {
+    float v[];
+} eyes;
@@ -0,0 +1,10 @@
+#define PI 3.1415926538
+#define TAU 6.2831853071
+
+vec3 rgb(int r, int g, int b) {
+    return vec3(float(r) / 255.0, float(g) / 255.0, float(b) / 255.0);
+}
+
+vec2 rotate2(vec2 p, float angle) {
+    return mat2(cos(angle), -sin(angle), sin(angle), cos(angle)) * p;
+}
@@ -0,0 +1,29 @@
+#version 450 
+layout (location = 0) out vec4 out_color;
+  
+layout (location = 0) in vec2 uv;   
+layout (binding  = 0) uniform UniformParameters {
+    float time;
+} u;
+layout (binding  = 1) readonly buffer fftBuffer{
+    float v[];
+} fft;
+layout (binding  = 2) readonly buffer SliderParameters{
+    float v[];
+} sliders;
+
+layout (set = 1, binding = 0) uniform texture2D buffer_a;
+layout (set = 1, binding = 4) uniform sampler buffer_sampler;
+
+
+#define PI 3.1415926538
+#define TAU 6.2831853071
+
+void main(){
+    vec2 head = vec2(0.5) + 0.3 * vec2(cos(u.time), sin(u.time * 1.7));
+    float d = length(uv - head);
+    float brush = smoothstep(0.03, 0.02, d);
+
+    vec4 previous = texture(sampler2D(buffer_a, buffer_sampler), uv);
+    out_color = vec4(max(previous.rgb * 0.985, vec3(brush)), 1.0);
+} 
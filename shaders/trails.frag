@@ -0,0 +1,31 @@
+#version 450 
+layout (location = 0) out vec4 out_color;
+  
+layout (location = 0) in vec2 uv;   
+layout (binding  = 0) uniform UniformParameters {
+    float time;
+} u;
+layout (binding  = 1) readonly buffer fftBuffer{
+    float v[];
+} fft;
+layout (binding  = 2) readonly buffer SliderParameters{
+    float v[];
+} sliders;
+
+layout (set = 1, binding = 0) uniform texture2D buffer_a;
+layout (set = 1, binding = 4) uniform sampler buffer_sampler;
+
+
+#define PI 3.1415926538
+#define TAU 6.2831853071
+
+vec3 rgb(int r, int g, int b) {
+    return vec3(float(r) / 255.0, float(g) / 255.0, float(b) / 255.0);
+}
+
+void main(){
+    float trail = texture(sampler2D(buffer_a, buffer_sampler), uv).r;
+    vec3 background = rgb(10, 10, 30);
+    vec3 color = mix(rgb(255, 80, 20), rgb(255, 230, 120), trail);
+    out_color = vec4(mix(background, color, trail), 1.0);
+} 
@@ -0,0 +1,27 @@
+struct UniformParameters {
+    time: f32,
+    fft: f32,
+    time_delta: f32,
+    frame: f32,
+    resolution: vec2<f32>,
+    aspect: f32,
+    fft_length: f32,
+    mouse: vec4<f32>,
+    date: vec4<f32>,
+}
+
+struct fftBuffer {
+    v: array<f32>,
+}
+
+@group(0) @binding(0) var<uniform> u: UniformParameters;
+@group(0) @binding(1) var<storage, read> fft: fftBuffer;
+
+@fragment
+fn main(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
+    let p = (uv - 0.5) * vec2<f32>(u.aspect, 1.0);
+    let band = u32(clamp(uv.x, 0.0, 1.0) * (u.fft_length - 1.0));
+    let ring = 0.5 + 0.5 * sin(length(p) * 20.0 - u.time * 3.0);
+    let color = vec3<f32>(uv, ring) * (0.5 + fft.v[band]);
+    return vec4<f32>(color, 1.0);
+}
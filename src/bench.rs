@@ -0,0 +1,265 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use wgpu::{Backends, InstanceFlags, TextureFormat};
+
+use crate::{
+    camera::Camera,
+    playlist::{playlist_entries, PlaylistOptions},
+    profiler::TimedPass,
+    shader::{ShaderOptions, Shaders},
+    state::{State, WindowSize},
+};
+
+/// Shader the renderer starts with, every benchmarked shader replaces it.
+const IDLE_SHADER: &str =
+    "#version 450\nlayout (location = 0) out vec4 out_color;\nvoid main() {\n    out_color = vec4(0.0);\n}\n";
+
+/// Renders every shader offscreen and reports their frame times.
+#[derive(clap::Args, Debug)]
+pub struct BenchOptions {
+    /// Directory of shaders or a playlist file
+    #[arg(default_value = "shaders")]
+    pub shaders: PathBuf,
+    /// Frames measured per shader
+    #[arg(long, default_value_t = 120, value_parser = clap::value_parser!(u32).range(1..))]
+    pub frames: u32,
+    /// Frames rendered before measuring
+    #[arg(long, default_value_t = 10)]
+    pub warmup: u32,
+    #[arg(long, default_value_t = 1280, value_parser = clap::value_parser!(u32).range(1..))]
+    pub width: u32,
+    #[arg(long, default_value_t = 720, value_parser = clap::value_parser!(u32).range(1..))]
+    pub height: u32,
+    /// Print the results as json instead of a table
+    #[arg(long)]
+    pub json: bool,
+}
+
+pub struct BenchResult {
+    pub path: PathBuf,
+    /// milliseconds to load and validate the shader and create its pipelines
+    pub compile: f32,
+    /// milliseconds from submitting a frame until the GPU finished it
+    pub frames: Vec<f32>,
+    /// average milliseconds of the shader pass measured on the GPU, needs timestamp queries
+    pub gpu: Option<f32>,
+    pub error: Option<String>,
+}
+
+impl BenchResult {
+    pub fn mean(&self) -> Option<f32> {
+        (!self.frames.is_empty())
+            .then(|| self.frames.iter().sum::<f32>() / self.frames.len() as f32)
+    }
+
+    pub fn p95(&self) -> Option<f32> {
+        percentile(&self.frames, 0.95)
+    }
+}
+
+/// Nearest rank percentile, `None` without values.
+fn percentile(values: &[f32], p: f32) -> Option<f32> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let rank = (p * sorted.len() as f32).ceil() as usize;
+    sorted.get(rank.max(1) - 1).copied()
+}
+
+pub async fn bench(
+    options: &BenchOptions,
+    shader_options: &ShaderOptions,
+    pi: bool,
+) -> Result<Vec<BenchResult>, String> {
+    let paths = playlist_entries(&options.shaders)
+        .map_err(|e| format!("{}: {e}", options.shaders.display()))?;
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: if pi { Backends::GL } else { Backends::VULKAN },
+        dx12_shader_compiler: wgpu::Dx12Compiler::Fxc,
+        flags: InstanceFlags::empty(),
+        gles_minor_version: wgpu::Gles3MinorVersion::Automatic,
+    });
+    let (mut state, view, _texture) = State::new(
+        instance,
+        None,
+        Some(TextureFormat::Rgba8UnormSrgb),
+        WindowSize {
+            width: options.width,
+            height: options.height,
+        },
+        &[Shaders::from_main("idle.frag", IDLE_SHADER)],
+        &[Camera::default()],
+        &Arc::new(Mutex::new(vec![0.0; 1024])),
+        0.0,
+        Arc::new(Mutex::new(Vec::new())),
+        &[],
+        true,
+        false,
+        wgpu::PresentMode::Fifo,
+        PlaylistOptions {
+            interval: None,
+            crossfade: 0.0,
+        },
+    )
+    .await?;
+    let view = view.unwrap();
+    state.enable_profiling(None).map_err(|e| e.to_string())?;
+    let results = paths
+        .into_iter()
+        .map(|path| {
+            let start = Instant::now();
+            let loaded =
+                state.reload(Shaders::load(&path, shader_options).map_err(|e| e.to_string()));
+            let compile = start.elapsed().as_secs_f32() * 1000.0;
+            let mut result = BenchResult {
+                path,
+                compile,
+                frames: Vec::new(),
+                gpu: None,
+                error: loaded.err(),
+            };
+            if result.error.is_some() {
+                return result;
+            }
+            state.reset_time();
+            for frame in 0..options.warmup + options.frames {
+                if frame == options.warmup {
+                    state.gpu_times();
+                }
+                state.set_time(frame as f32 / 60.0);
+                let start = Instant::now();
+                state.render(Some(&view), None).unwrap();
+                state.wait_idle();
+                if frame >= options.warmup {
+                    result.frames.push(start.elapsed().as_secs_f32() * 1000.0);
+                }
+            }
+            result.gpu = state
+                .gpu_times()
+                .and_then(|times| times[TimedPass::Main as usize]);
+            log::info!("{}: {:?} ms", result.path.display(), result.mean());
+            result
+        })
+        .collect();
+    Ok(results)
+}
+
+fn format_ms(value: Option<f32>) -> String {
+    value.map(|v| format!("{v:.2}")).unwrap_or("-".into())
+}
+
+pub fn table(results: &[BenchResult]) -> String {
+    let width = results
+        .iter()
+        .map(|r| r.path.display().to_string().len())
+        .max()
+        .unwrap_or(0)
+        .max("shader".len());
+    let mut table = format!(
+        "{:width$}  {:>10}  {:>8}  {:>8}  {:>8}  status\n",
+        "shader", "compile ms", "mean ms", "p95 ms", "gpu ms"
+    );
+    for result in results {
+        let status = match &result.error {
+            Some(e) => format!("failed: {}", e.lines().next().unwrap_or_default()),
+            None => "ok".to_string(),
+        };
+        table += &format!(
+            "{:width$}  {:>10.1}  {:>8}  {:>8}  {:>8}  {status}\n",
+            result.path.display(),
+            result.compile,
+            format_ms(result.mean()),
+            format_ms(result.p95()),
+            format_ms(result.gpu),
+        );
+    }
+    let failed = results.iter().filter(|r| r.error.is_some()).count();
+    table += &format!("{} shaders, {failed} failed\n", results.len());
+    table
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => json += "\\\"",
+            '\\' => json += "\\\\",
+            '\n' => json += "\\n",
+            c if (c as u32) < 0x20 => json += &format!("\\u{:04x}", c as u32),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn json_number(value: Option<f32>) -> String {
+    value
+        .filter(|v| v.is_finite())
+        .map(|v| format!("{v:.3}"))
+        .unwrap_or("null".into())
+}
+
+pub fn json(options: &BenchOptions, results: &[BenchResult]) -> String {
+    let shaders: Vec<_> = results
+        .iter()
+        .map(|r| {
+            format!(
+                "{{\"path\": {}, \"compile_ms\": {}, \"mean_ms\": {}, \"p95_ms\": {}, \"gpu_ms\": {}, \"error\": {}}}",
+                json_string(&r.path.display().to_string()),
+                json_number(Some(r.compile)),
+                json_number(r.mean()),
+                json_number(r.p95()),
+                json_number(r.gpu),
+                r.error.as_deref().map(json_string).unwrap_or("null".into()),
+            )
+        })
+        .collect();
+    format!(
+        "{{\"width\": {}, \"height\": {}, \"frames\": {}, \"shaders\": [\n  {}\n]}}\n",
+        options.width,
+        options.height,
+        options.frames,
+        shaders.join(",\n  ")
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::{json_string, percentile, table, BenchResult};
+
+    #[test]
+    fn summarizes_frame_times() {
+        let frames: Vec<f32> = (1..=20).map(|i| i as f32).collect();
+        assert_eq!(percentile(&frames, 0.95), Some(19.0));
+        assert_eq!(percentile(&[3.0], 0.95), Some(3.0));
+        assert_eq!(percentile(&[], 0.95), None);
+
+        let results = [
+            BenchResult {
+                path: PathBuf::from("shaders/a.frag"),
+                compile: 12.0,
+                frames,
+                gpu: None,
+                error: None,
+            },
+            BenchResult {
+                path: PathBuf::from("shaders/broken.frag"),
+                compile: 1.0,
+                frames: Vec::new(),
+                gpu: None,
+                error: Some("broken.frag:3: error\n  details".into()),
+            },
+        ];
+        let table = table(&results);
+        assert!(table.contains("10.50"), "{table}");
+        assert!(table.contains("failed: broken.frag:3: error\n"), "{table}");
+        assert!(table.ends_with("2 shaders, 1 failed\n"), "{table}");
+        assert_eq!(json_string("a \"b\"\n"), "\"a \\\"b\\\"\\n\"");
+    }
+}
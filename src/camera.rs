@@ -0,0 +1,239 @@
+use std::{fmt, path::Path, path::PathBuf};
+
+/// Radians the camera turns per pixel of mouse drag.
+const DRAG_SENSITIVITY: f32 = 0.005;
+/// Closest the orbit camera gets to its target.
+const MIN_DISTANCE: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraMode {
+    /// moves freely, dragging looks around
+    Fly,
+    /// circles the point `distance` in front of it, dragging turns around that point
+    Orbit,
+}
+
+/// Camera of 3D shaders, passed to them as `camera_*` uniforms. y is up, with a yaw of 0 the
+/// camera looks along +z.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub mode: CameraMode,
+    pub position: [f32; 3],
+    /// degrees around the y axis
+    pub yaw: f32,
+    /// degrees above the horizon
+    pub pitch: f32,
+    /// vertical field of view in degrees
+    pub fov: f32,
+    /// distance of the orbit target, also scales the movement speed
+    pub distance: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera {
+            mode: CameraMode::Fly,
+            position: [0.0; 3],
+            yaw: 0.0,
+            pitch: 0.0,
+            fov: 60.0,
+            distance: 10.0,
+        }
+    }
+}
+
+/// `shaders/egg_3d.frag` -> `shaders/egg_3d.camera`
+pub fn camera_path(path: &Path) -> PathBuf {
+    path.with_extension("camera")
+}
+
+fn add(a: [f32; 3], b: [f32; 3], scale: f32) -> [f32; 3] {
+    [
+        a[0] + b[0] * scale,
+        a[1] + b[1] * scale,
+        a[2] + b[2] * scale,
+    ]
+}
+
+impl Camera {
+    pub fn forward(&self) -> [f32; 3] {
+        let (yaw, pitch) = (self.yaw.to_radians(), self.pitch.to_radians());
+        [
+            yaw.sin() * pitch.cos(),
+            pitch.sin(),
+            yaw.cos() * pitch.cos(),
+        ]
+    }
+
+    pub fn right(&self) -> [f32; 3] {
+        let yaw = self.yaw.to_radians();
+        [yaw.cos(), 0.0, -yaw.sin()]
+    }
+
+    /// `forward × right`
+    pub fn up(&self) -> [f32; 3] {
+        let (f, r) = (self.forward(), self.right());
+        [
+            f[1] * r[2] - f[2] * r[1],
+            f[2] * r[0] - f[0] * r[2],
+            f[0] * r[1] - f[1] * r[0],
+        ]
+    }
+
+    fn target(&self) -> [f32; 3] {
+        add(self.position, self.forward(), self.distance)
+    }
+
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            CameraMode::Fly => CameraMode::Orbit,
+            CameraMode::Orbit => CameraMode::Fly,
+        };
+    }
+
+    /// Mouse drag by `delta` pixels, y pointing down. Looks around in fly mode and turns around
+    /// the target in orbit mode.
+    pub fn drag(&mut self, delta: (f32, f32)) {
+        let target = self.target();
+        let yaw = delta.0 * DRAG_SENSITIVITY.to_degrees();
+        self.yaw = match self.mode {
+            CameraMode::Fly => self.yaw + yaw,
+            CameraMode::Orbit => self.yaw - yaw,
+        } % 360.0;
+        self.pitch = (self.pitch - delta.1 * DRAG_SENSITIVITY.to_degrees()).clamp(-89.0, 89.0);
+        if self.mode == CameraMode::Orbit {
+            self.position = add(target, self.forward(), -self.distance);
+        }
+    }
+
+    /// Moves by `movement` (right, up, forward) times the speed for `seconds`. In orbit mode
+    /// forward zooms towards the target and the other directions move the target along.
+    pub fn fly(&mut self, movement: [f32; 3], seconds: f32) {
+        let speed = self.distance * 0.5 * seconds;
+        let (right, up, forward) = (self.right(), self.up(), self.forward());
+        self.position = add(self.position, right, movement[0] * speed);
+        self.position = add(self.position, up, movement[1] * speed);
+        match self.mode {
+            CameraMode::Fly => self.position = add(self.position, forward, movement[2] * speed),
+            CameraMode::Orbit => {
+                let target = self.target();
+                self.distance =
+                    (self.distance * (-movement[2] * 0.5 * seconds).exp()).max(MIN_DISTANCE);
+                self.position = add(target, forward, -self.distance);
+            }
+        }
+    }
+
+    /// Reads a camera saved with `save`, `None` if the file does not exist. Missing values keep
+    /// their default.
+    #[allow(dead_code)]
+    pub fn load(path: &Path) -> std::io::Result<Option<Camera>> {
+        let Ok(source) = std::fs::read_to_string(path) else {
+            return Ok(None);
+        };
+        source
+            .parse()
+            .map(Some)
+            .map_err(|e| std::io::Error::other(format!("{}:{e}", path.display())))
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for Camera {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self.mode {
+            CameraMode::Fly => "fly",
+            CameraMode::Orbit => "orbit",
+        };
+        let [x, y, z] = self.position;
+        writeln!(f, "mode {mode}")?;
+        writeln!(f, "position {x} {y} {z}")?;
+        writeln!(f, "yaw {}", self.yaw)?;
+        writeln!(f, "pitch {}", self.pitch)?;
+        writeln!(f, "fov {}", self.fov)?;
+        writeln!(f, "distance {}", self.distance)
+    }
+}
+
+/// One `name values...` per line, `#` starts a comment line.
+impl std::str::FromStr for Camera {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut camera = Camera::default();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |e: &str| format!("{}: {e}", i + 1);
+            let mut words = line.split_whitespace();
+            let name = words.next().unwrap();
+            let values: Vec<&str> = words.collect();
+            if name == "mode" {
+                camera.mode = match values[..] {
+                    ["fly"] => CameraMode::Fly,
+                    ["orbit"] => CameraMode::Orbit,
+                    _ => return Err(error("mode is `fly` or `orbit`")),
+                };
+                continue;
+            }
+            let numbers = values
+                .iter()
+                .map(|v| v.parse::<f32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| error(&e.to_string()))?;
+            match (name, &numbers[..]) {
+                ("position", &[x, y, z]) => camera.position = [x, y, z],
+                ("yaw", &[yaw]) => camera.yaw = yaw,
+                ("pitch", &[pitch]) => camera.pitch = pitch.clamp(-89.0, 89.0),
+                ("fov", &[fov]) if fov > 0.0 && fov < 180.0 => camera.fov = fov,
+                ("distance", &[distance]) => camera.distance = distance.max(MIN_DISTANCE),
+                ("position" | "yaw" | "pitch" | "fov" | "distance", _) => {
+                    return Err(error(&format!("invalid {name} `{}`", values.join(" "))))
+                }
+                _ => return Err(error(&format!("unknown value `{name}`"))),
+            }
+        }
+        Ok(camera)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Camera, CameraMode};
+
+    fn assert_close(a: [f32; 3], b: [f32; 3]) {
+        assert!(
+            a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-4),
+            "{a:?} {b:?}"
+        );
+    }
+
+    #[test]
+    fn orbit_keeps_the_target() {
+        let mut camera = Camera {
+            mode: CameraMode::Orbit,
+            position: [0.0, 0.0, -10.0],
+            ..Default::default()
+        };
+        assert_close(camera.up(), [0.0, 1.0, 0.0]);
+        camera.drag((300.0, -120.0));
+        assert_close(camera.target(), [0.0; 3]);
+        assert!(camera.pitch > 0.0);
+        camera.fly([0.0, 0.0, 1.0], 1.0);
+        assert_close(camera.target(), [0.0; 3]);
+        assert!(camera.distance < 10.0);
+
+        let saved: Camera = camera.to_string().parse().unwrap();
+        assert_eq!(saved, camera);
+        assert!("fov 0"
+            .parse::<Camera>()
+            .unwrap_err()
+            .starts_with("1: invalid fov"));
+        assert!("# comment\nzoom 2".parse::<Camera>().is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use std::{path::PathBuf, str::FromStr};
+
+use wgpu::{util::DeviceExt, AddressMode, Device, FilterMode, Queue, Sampler, TextureView};
+
+/// Number of image inputs, `--channel0` to `--channel3`.
+pub const CHANNELS: usize = 4;
+
+/// `--channel0 noise.png,nearest,repeat`: an image file followed by optional filter
+/// (`linear`, `nearest`) and wrap mode (`clamp`, `repeat`, `mirror`).
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
+pub struct ChannelSpec {
+    pub path: PathBuf,
+    pub filter: FilterMode,
+    pub wrap: AddressMode,
+}
+
+impl FromStr for ChannelSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(',');
+        let mut spec = ChannelSpec {
+            path: PathBuf::from(parts.next().unwrap_or_default()),
+            filter: FilterMode::Linear,
+            wrap: AddressMode::ClampToEdge,
+        };
+        for option in parts {
+            match option {
+                "linear" => spec.filter = FilterMode::Linear,
+                "nearest" => spec.filter = FilterMode::Nearest,
+                "clamp" => spec.wrap = AddressMode::ClampToEdge,
+                "repeat" => spec.wrap = AddressMode::Repeat,
+                "mirror" => spec.wrap = AddressMode::MirrorRepeat,
+                _ => {
+                    return Err(format!(
+                        "unknown channel option `{option}`, expected linear, nearest, clamp, repeat or mirror"
+                    ))
+                }
+            }
+        }
+        Ok(spec)
+    }
+}
+
+/// Decoded rgba8 pixels of a channel, rows bottom to top to match `uv`.
+pub struct ChannelImage {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+    pub filter: FilterMode,
+    pub wrap: AddressMode,
+}
+
+#[cfg(all(unix, not(target_family = "wasm")))]
+impl ChannelImage {
+    /// Images larger than the texture size limit the device is requested with are rejected.
+    #[allow(dead_code)]
+    pub fn load(spec: &ChannelSpec) -> Result<ChannelImage, String> {
+        let image = image::open(&spec.path)
+            .map_err(|e| format!("{}: {e}", spec.path.display()))?
+            .flipv()
+            .into_rgba8();
+        let max_size = wgpu::Limits::default().max_texture_dimension_2d;
+        if image.width() > max_size || image.height() > max_size {
+            return Err(format!(
+                "{}: the image is {}x{}, larger than the maximum texture size of {max_size}",
+                spec.path.display(),
+                image.width(),
+                image.height()
+            ));
+        }
+        Ok(ChannelImage {
+            width: image.width(),
+            height: image.height(),
+            rgba: image.into_raw(),
+            filter: spec.filter,
+            wrap: spec.wrap,
+        })
+    }
+}
+
+/// Textures and samplers of all channels. Channels without an image are a single black pixel.
+pub struct Channels {
+    pub views: Vec<TextureView>,
+    pub samplers: Vec<Sampler>,
+}
+
+impl Channels {
+    pub fn new(device: &Device, queue: &Queue, images: &[Option<ChannelImage>]) -> Channels {
+        let black = ChannelImage {
+            width: 1,
+            height: 1,
+            rgba: vec![0, 0, 0, 255],
+            filter: FilterMode::Linear,
+            wrap: AddressMode::ClampToEdge,
+        };
+        let images: Vec<_> = (0..CHANNELS)
+            .map(|i| images.get(i).and_then(Option::as_ref).unwrap_or(&black))
+            .collect();
+        let views = images
+            .iter()
+            .map(|image| {
+                device
+                    .create_texture_with_data(
+                        queue,
+                        &wgpu::TextureDescriptor {
+                            label: Some("channel texture"),
+                            size: wgpu::Extent3d {
+                                width: image.width,
+                                height: image.height,
+                                depth_or_array_layers: 1,
+                            },
+                            mip_level_count: 1,
+                            sample_count: 1,
+                            dimension: wgpu::TextureDimension::D2,
+                            format: wgpu::TextureFormat::Rgba8Unorm,
+                            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                                | wgpu::TextureUsages::COPY_DST,
+                            view_formats: &[],
+                        },
+                        wgpu::util::TextureDataOrder::LayerMajor,
+                        &image.rgba,
+                    )
+                    .create_view(&Default::default())
+            })
+            .collect();
+        let samplers = images
+            .iter()
+            .map(|image| {
+                device.create_sampler(&wgpu::SamplerDescriptor {
+                    label: Some("channel sampler"),
+                    address_mode_u: image.wrap,
+                    address_mode_v: image.wrap,
+                    address_mode_w: image.wrap,
+                    mag_filter: image.filter,
+                    min_filter: image.filter,
+                    mipmap_filter: FilterMode::Nearest,
+                    ..Default::default()
+                })
+            })
+            .collect();
+        Channels { views, samplers }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ChannelSpec;
+    use wgpu::{AddressMode, FilterMode};
+
+    #[test]
+    fn parses_channel_options() {
+        let spec: ChannelSpec = "textures/noise.png,nearest,repeat".parse().unwrap();
+        assert_eq!(spec.path.to_str(), Some("textures/noise.png"));
+        assert_eq!(spec.filter, FilterMode::Nearest);
+        assert_eq!(spec.wrap, AddressMode::Repeat);
+        let spec: ChannelSpec = "photo.jpg".parse().unwrap();
+        assert_eq!(spec.filter, FilterMode::Linear);
+        assert_eq!(spec.wrap, AddressMode::ClampToEdge);
+        assert!("photo.jpg,blurry".parse::<ChannelSpec>().is_err());
+    }
+}
@@ -0,0 +1,109 @@
+#[cfg(all(unix, not(target_family = "wasm")))]
+use std::time::Instant;
+#[cfg(target_family = "wasm")]
+use web_time::Instant;
+
+/// Seconds a single frame step moves the clock.
+pub const FRAME_STEP: f32 = 1.0 / 60.0;
+/// Largest factor the playback speed can be changed by, in both directions.
+const MAX_SPEED: f32 = 16.0;
+
+/// Shader time that can be paused, stepped, seeked and played at other speeds, also backwards.
+#[derive(Clone, Copy, Debug)]
+pub struct Clock {
+    /// shader time at `anchor`
+    base: f32,
+    anchor: Instant,
+    pub speed: f32,
+    pub paused: bool,
+}
+
+impl Clock {
+    pub fn new(time: f32) -> Clock {
+        Clock {
+            base: time,
+            anchor: Instant::now(),
+            speed: 1.0,
+            paused: false,
+        }
+    }
+
+    pub fn time(&self) -> f32 {
+        if self.paused {
+            self.base
+        } else {
+            self.base + self.anchor.elapsed().as_secs_f32() * self.speed
+        }
+    }
+
+    /// Moves the elapsed time into `base`, so changes only apply from now on.
+    fn rebase(&mut self) {
+        self.base = self.time();
+        self.anchor = Instant::now();
+    }
+
+    pub fn set(&mut self, time: f32) {
+        self.base = time;
+        self.anchor = Instant::now();
+    }
+
+    /// Stops the clock at `time`, used for offline renders.
+    pub fn pause_at(&mut self, time: f32) {
+        self.set(time);
+        self.paused = true;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.rebase();
+        self.paused = !self.paused;
+    }
+
+    pub fn seek(&mut self, seconds: f32) {
+        self.rebase();
+        self.base += seconds;
+    }
+
+    /// Pauses and moves the clock by `frames` frames, negative values step backwards.
+    pub fn step(&mut self, frames: i32) {
+        self.rebase();
+        self.paused = true;
+        self.base += frames as f32 * FRAME_STEP;
+    }
+
+    /// Playback speed, negative values play backwards. The magnitude is kept between 1/16 and
+    /// 16.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.rebase();
+        self.speed = speed.signum() * speed.abs().clamp(1.0 / MAX_SPEED, MAX_SPEED);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Clock, FRAME_STEP};
+
+    #[test]
+    fn transport_controls_move_the_clock() {
+        let mut clock = Clock::new(10.0);
+        clock.pause_at(10.0);
+        clock.seek(2.5);
+        assert_eq!(clock.time(), 12.5);
+        clock.step(3);
+        clock.step(-1);
+        assert!((clock.time() - (12.5 + 2.0 * FRAME_STEP)).abs() < 1e-6);
+        clock.set_speed(-100.0);
+        assert_eq!(clock.speed, -16.0);
+        clock.set_speed(0.01);
+        assert_eq!(clock.speed, 1.0 / 16.0);
+
+        let before = clock.time();
+        clock.set_speed(-1.0);
+        clock.toggle_pause();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        clock.toggle_pause();
+        assert!(clock.paused);
+        assert!(clock.time() < before, "{}", clock.time());
+        clock.set(0.0);
+        assert_eq!(clock.time(), 0.0);
+    }
+}
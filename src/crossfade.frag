@@ -0,0 +1,14 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 out_color;
+layout (binding = 0) uniform texture2D outgoing;
+layout (binding = 1) uniform sampler outgoing_sampler;
+layout (binding = 2) uniform Crossfade {
+    float opacity;
+} fade;
+
+void main() {
+    // texture rows start at the top, uv at the bottom
+    vec3 color = texture(sampler2D(outgoing, outgoing_sampler), vec2(uv.x, 1.0 - uv.y)).rgb;
+    out_color = vec4(color, fade.opacity);
+}
@@ -0,0 +1,33 @@
+#version 450
+layout (location = 0) out vec4 out_color;
+
+layout (location = 0) in vec2 uv;
+layout (binding = 0) uniform texture2D text;
+layout (binding = 1) uniform sampler text_sampler;
+layout (binding = 2) uniform ErrorParameters {
+    // window pixels per pixel of the text
+    float scale;
+    // number of lines in the text
+    float lines;
+} u;
+
+// cell height of font.rs
+#define LINE_HEIGHT 10.0
+#define MARGIN 4.0
+
+void main(){
+    // the origin of gl_FragCoord is the top left corner
+    vec2 pixel = gl_FragCoord.xy / u.scale - vec2(MARGIN);
+    if (pixel.y > u.lines * LINE_HEIGHT + MARGIN) {
+        discard;
+    }
+    float stripes = step(0.5, fract((uv.x * 40.0 + uv.y * 2.0)));
+    vec3 color = mix(vec3(0.6, 0.0, 0.0), vec3(0.4, 0.0, 0.0), stripes);
+    ivec2 texel = ivec2(floor(pixel));
+    ivec2 size = textureSize(sampler2D(text, text_sampler), 0);
+    float ink = 0.0;
+    if (all(greaterThanEqual(texel, ivec2(0))) && all(lessThan(texel, size))) {
+        ink = texelFetch(sampler2D(text, text_sampler), texel, 0).r;
+    }
+    out_color = vec4(mix(color, vec3(1.0), ink), mix(0.85, 1.0, ink));
+}
@@ -1,15 +1,31 @@
 mod audio;
+mod camera;
+mod channel;
+mod clock;
+mod font;
 mod model;
+mod multipass;
+mod playlist;
+mod profiler;
+mod reflection;
 mod render_pipeline;
 mod render_to_file;
 mod render_to_screen;
 mod renderable;
+mod shader;
 mod sound;
 mod state;
+mod tiles;
+mod upscale;
 mod util;
 
-use render_to_screen::render_to_screen;
+use camera::Camera;
+use playlist::PlaylistOptions;
+use render_to_screen::{render_to_screen, PresentMode, WindowMode, WindowOptions};
+use shader::Shaders;
+use state::WindowSize;
 use std::sync::{Arc, Mutex};
+use upscale::RenderScale;
 
 #[cfg(all(unix, not(target_family = "wasm")))]
 use std::thread::{spawn, JoinHandle};
@@ -61,14 +77,39 @@ pub fn run() {
     let o = Arc::new(Mutex::new(vec![0.0; 1024]));
     let _ = background(o.clone());
 
-    let fragment_shader = include_str!("../shaders/nuage_nuage.frag");
-    render_to_screen(
+    let shaders = Shaders::from_main(
+        "nuage_nuage.frag",
+        include_str!("../shaders/nuage_nuage.frag"),
+    );
+    if let Err(e) = render_to_screen(
         false,
         false,
         true,
-        fragment_shader,
+        &[shaders],
+        &[Camera::default()],
         &o,
         0.0,
         Arc::new(Mutex::new(Vec::new())),
-    )
+        &[],
+        RenderScale::new(1.0, None),
+        WindowOptions {
+            mode: WindowMode::Windowed,
+            size: WindowSize {
+                width: 1920,
+                height: 1080,
+            },
+            monitor: 0,
+            present_mode: PresentMode::Fifo,
+            max_fps: None,
+        },
+        PlaylistOptions {
+            interval: None,
+            crossfade: 0.0,
+        },
+        &[],
+        None,
+        None,
+    ) {
+        log::error!("{e}");
+    }
 }
@@ -1,12 +1,24 @@
 mod audio;
+mod bench;
+mod camera;
+mod channel;
+mod clock;
 mod eye;
+mod font;
 mod model;
+mod multipass;
+mod playlist;
+mod profiler;
+mod reflection;
 mod render_pipeline;
 mod render_to_file;
 mod render_to_screen;
 mod renderable;
+mod shader;
 mod sound;
 mod state;
+mod tiles;
+mod upscale;
 mod util;
 
 use std::{
@@ -15,21 +27,103 @@ use std::{
     thread::{self, JoinHandle},
 };
 
+use bench::BenchOptions;
+use camera::{camera_path, Camera};
+use channel::{ChannelImage, ChannelSpec};
 use clap::Parser;
 use cpal::Stream;
-use render_to_file::render_to_file;
-use render_to_screen::render_to_screen;
+use playlist::{playlist_entries, PlaylistOptions};
+use render_to_file::{render_to_file, FrameSequence, OutputFormat};
+use render_to_screen::{render_to_screen, PresentMode, WindowMode, WindowOptions};
+use shader::{parse_define, ShaderOptions, Shaders};
+use state::WindowSize;
+use upscale::RenderScale;
 
 #[derive(Parser, Debug)]
 struct Opt {
+    #[command(subcommand)]
+    command: Option<Command>,
     #[arg(long, default_value = "shaders/shader.frag")]
     shader_path: PathBuf,
+    /// Directory of shaders or a playlist file listing one shader per line, replaces
+    /// --shader-path. Files are rendered with the first entry.
+    #[arg(long)]
+    playlist: Option<PathBuf>,
+    /// Seconds each playlist entry is shown, without it entries are switched with the arrow keys
+    #[arg(long)]
+    playlist_interval: Option<f32>,
+    /// Seconds of the crossfade between playlist entries
+    #[arg(long, default_value_t = 2.0)]
+    crossfade: f32,
+    /// Directory searched for `#include`d files after the directory of the including shader,
+    /// can be repeated
+    #[arg(long)]
+    include_dir: Vec<PathBuf>,
+    /// Preprocessor define `NAME=VALUE` for glsl shaders, can be repeated
+    #[arg(short = 'D', long = "define", value_parser = parse_define)]
+    defines: Vec<(String, String)>,
+    /// Camera file of 3D shaders, replaces `<shader>.camera` and is where F5 saves the camera
+    #[arg(long)]
+    camera: Option<PathBuf>,
     #[arg(long)]
     srgb: bool,
     #[arg(long)]
     fps: bool,
+    /// Print the GPU time of the render passes next to the frame time, needs timestamp query
+    /// support
+    #[arg(long)]
+    profile: bool,
+    /// Write the GPU time of every frame to this csv file, implies --profile
+    #[arg(long)]
+    profile_csv: Option<PathBuf>,
     #[arg(long)]
     image_path: Option<PathBuf>,
+    /// Size of rendered images and of the window with --window-mode windowed
+    #[arg(long, default_value_t = 1920, value_parser = clap::value_parser!(u32).range(1..))]
+    width: u32,
+    #[arg(long, default_value_t = 1080, value_parser = clap::value_parser!(u32).range(1..))]
+    height: u32,
+    /// Render a numbered png sequence into the directory given by --image-path
+    #[arg(long)]
+    frames: Option<u32>,
+    #[arg(long, default_value_t = 60.0)]
+    frame_rate: f32,
+    /// Render images at N times the resolution and downsample them
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=8))]
+    ssaa: u32,
+    /// Render images in tiles of at most this many pixels per side, defaults to the largest
+    /// texture the GPU supports
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    tile_size: Option<u32>,
+    /// File format of --image-path, defaults to exr for .exr paths and png otherwise, exr32
+    /// stores 32 bit floats
+    #[arg(long, value_enum)]
+    output_format: Option<OutputFormat>,
+    /// Image bound as `channel0` in the shader: `path[,linear|nearest][,clamp|repeat|mirror]`
+    #[arg(long)]
+    channel0: Option<ChannelSpec>,
+    #[arg(long)]
+    channel1: Option<ChannelSpec>,
+    #[arg(long)]
+    channel2: Option<ChannelSpec>,
+    #[arg(long)]
+    channel3: Option<ChannelSpec>,
+    /// Render the shader at this fraction of the window size, between 0.25 and 1, and upscale it
+    #[arg(long, default_value_t = 1.0)]
+    render_scale: f32,
+    /// Adjust the render scale automatically to hold this frame rate
+    #[arg(long)]
+    target_fps: Option<f32>,
+    #[arg(long, value_enum, default_value_t = WindowMode::Exclusive)]
+    window_mode: WindowMode,
+    /// Index of the monitor to open the window on
+    #[arg(long, default_value_t = 0)]
+    monitor: usize,
+    #[arg(long, value_enum, default_value_t = PresentMode::Fifo)]
+    present_mode: PresentMode,
+    /// Redraw at most this many times per second to save power
+    #[arg(long)]
+    max_fps: Option<f32>,
     #[arg(long)]
     pi: bool,
     #[arg(long, default_value_t = 0.0)]
@@ -42,6 +136,13 @@ struct Opt {
     fft_voyage_voyage: bool,
 }
 
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Render every shader of a directory offscreen and print their compile and frame times,
+    /// --pi, --include-dir and -D apply to it
+    Bench(BenchOptions),
+}
+
 #[allow(dead_code)]
 enum AudioStream {
     STREAM(Stream),
@@ -52,6 +153,107 @@ fn main() {
     env_logger::init();
 
     let opt = dbg!(Opt::parse());
+    let shader_options = ShaderOptions {
+        include_dirs: opt.include_dir.clone(),
+        defines: opt.defines.clone(),
+    };
+    if let Some(Command::Bench(options)) = &opt.command {
+        let results = pollster::block_on(bench::bench(options, &shader_options, opt.pi))
+            .unwrap_or_else(|e| {
+                eprintln!("{e}");
+                std::process::exit(1);
+            });
+        if options.json {
+            print!("{}", bench::json(options, &results));
+        } else {
+            print!("{}", bench::table(&results));
+        }
+        return;
+    }
+    let shader_paths = match &opt.playlist {
+        Some(playlist) => playlist_entries(playlist).unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }),
+        None => vec![opt.shader_path.clone()],
+    };
+    let shaders: Vec<_> = shader_paths
+        .iter()
+        .map(|path| {
+            let shaders = Shaders::load(path, &shader_options).unwrap_or_else(|e| {
+                eprintln!("{}: {e}", path.display());
+                std::process::exit(1);
+            });
+            if let Err(e) = shaders.check() {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+            shaders
+        })
+        .collect();
+    let cameras: Vec<_> = shader_paths
+        .iter()
+        .map(|path| {
+            let path = opt.camera.clone().unwrap_or_else(|| camera_path(path));
+            match Camera::load(&path) {
+                Ok(Some(camera)) => camera,
+                Ok(None) if opt.camera.is_some() => {
+                    eprintln!("{}: camera file not found", path.display());
+                    std::process::exit(1);
+                }
+                Ok(None) => Camera::default(),
+                Err(e) => {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                }
+            }
+        })
+        .collect();
+    let max_size = wgpu::Limits::default().max_texture_dimension_2d;
+    if opt
+        .tile_size
+        .is_some_and(|tile_size| tile_size.saturating_mul(opt.ssaa) > max_size)
+    {
+        eprintln!("--tile-size times --ssaa exceeds the maximum texture size of {max_size}");
+        std::process::exit(1);
+    }
+    if opt.frame_rate <= 0.0 || !opt.frame_rate.is_finite() {
+        eprintln!("--frame-rate must be positive");
+        std::process::exit(1);
+    }
+    if !(upscale::MIN_SCALE..=1.0).contains(&opt.render_scale) {
+        eprintln!(
+            "--render-scale must be between {} and 1",
+            upscale::MIN_SCALE
+        );
+        std::process::exit(1);
+    }
+    if opt.target_fps.is_some_and(|fps| fps <= 0.0 || fps.is_nan()) {
+        eprintln!("--target-fps must be positive");
+        std::process::exit(1);
+    }
+    if opt.max_fps.is_some_and(|fps| fps <= 0.0 || fps.is_nan()) {
+        eprintln!("--max-fps must be positive");
+        std::process::exit(1);
+    }
+    if opt
+        .playlist_interval
+        .is_some_and(|interval| interval <= 0.0 || interval.is_nan())
+    {
+        eprintln!("--playlist-interval must be positive");
+        std::process::exit(1);
+    }
+    let channels: Vec<_> = [&opt.channel0, &opt.channel1, &opt.channel2, &opt.channel3]
+        .into_iter()
+        .map(|spec| {
+            spec.as_ref().map(|spec| {
+                ChannelImage::load(spec).unwrap_or_else(|e| {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                })
+            })
+        })
+        .collect();
     let (_audio_stream, o) = if opt.fft_voyage_voyage {
         let o = Arc::new(Mutex::new(vec![0.0f32; 1024]));
         let o_c = o.clone();
@@ -77,18 +279,65 @@ fn main() {
         None
     };
 
-    let fragment_shader = std::fs::read_to_string(opt.shader_path).unwrap();
     match opt.image_path {
-        Some(_) => pollster::block_on(render_to_file(opt.srgb, &fragment_shader, &o, opt.time)),
+        Some(image_path) => pollster::block_on(render_to_file(
+            opt.srgb,
+            &shaders[0],
+            cameras[0],
+            &o,
+            opt.time,
+            WindowSize {
+                width: opt.width,
+                height: opt.height,
+            },
+            &image_path,
+            opt.frames.map(|frames| FrameSequence {
+                frames,
+                frame_rate: opt.frame_rate,
+            }),
+            &channels,
+            opt.ssaa,
+            opt.tile_size,
+            opt.output_format
+                .unwrap_or_else(|| OutputFormat::from_path(&image_path)),
+        ))
+        .unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }),
         None => render_to_screen(
             opt.fps,
             opt.pi,
             opt.srgb,
-            &fragment_shader,
+            &shaders,
+            &cameras,
             &o,
             opt.time,
             eye_positions,
-        ),
+            &channels,
+            RenderScale::new(opt.render_scale, opt.target_fps),
+            WindowOptions {
+                mode: opt.window_mode,
+                size: WindowSize {
+                    width: opt.width,
+                    height: opt.height,
+                },
+                monitor: opt.monitor,
+                present_mode: opt.present_mode,
+                max_fps: opt.max_fps,
+            },
+            PlaylistOptions {
+                interval: opt.playlist_interval,
+                crossfade: opt.crossfade,
+            },
+            &shader_paths,
+            opt.camera,
+            (opt.profile || opt.profile_csv.is_some()).then_some(opt.profile_csv),
+        )
+        .unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }),
     }
     eye_join_handle.join().unwrap();
 }
@@ -1,4 +1,4 @@
-use crevice::std430::AsStd430;
+use crevice::{std140::AsStd140, std430::AsStd430};
 use wgpu::{util::DeviceExt, Buffer, Device};
 
 #[derive(AsStd430, Clone)]
@@ -73,6 +73,37 @@ impl Vertex {
     }
 }
 
+/// Contents of the uniform block at binding 0, shared by all fragment shaders.
+#[derive(AsStd140, Clone, Copy)]
+pub struct Uniforms {
+    pub time: f32,
+    pub fft: f32,
+    pub time_delta: f32,
+    pub frame: f32,
+    pub resolution: mint::Vector2<f32>,
+    pub aspect: f32,
+    /// number of valid values in the fft storage buffer at binding 1
+    pub fft_length: f32,
+    /// Shadertoy convention: xy is the position while a button is held, zw the position of the
+    /// click, negated once the button is released. In pixels with the origin at the bottom left.
+    pub mouse: mint::Vector4<f32>,
+    /// year, month (0-11), day (1-31), seconds since midnight in UTC
+    pub date: mint::Vector4<f32>,
+    /// see `Camera`, the fov is vertical and in degrees
+    pub camera_position: mint::Vector3<f32>,
+    pub camera_fov: f32,
+    pub camera_forward: mint::Vector3<f32>,
+    pub camera_up: mint::Vector3<f32>,
+}
+
+pub fn create_uniforms_buffer(name: &str, device: &Device, uniforms: &Uniforms) -> Buffer {
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(name),
+        contents: uniforms.as_std140().as_bytes(),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    })
+}
+
 pub fn create_uniform_buffer(name: &str, device: &Device, values: [f32; 4]) -> Buffer {
     let mut bytes = vec![];
     let mut sphere_bytes_writer = crevice::std430::Writer::new(&mut bytes);
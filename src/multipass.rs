@@ -0,0 +1,216 @@
+use mint::Vector2;
+use wgpu::{
+    util::DeviceExt, BindGroup, BindGroupLayout, BindGroupLayoutDescriptor, Buffer, CommandEncoder,
+    Device, RenderPipeline, ShaderModule, Texture, TextureFormat, TextureView,
+};
+
+use crate::{model::Vertex, render_pipeline, state::WindowSize};
+
+/// Buffer passes render in a float format so feedback effects can accumulate without banding.
+pub const BUFFER_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+/// Binding of the sampler in the buffer bind group, the textures of buffer A-D use 0-3.
+pub const SAMPLER_BINDING: u32 = 4;
+
+/// Shadertoy style buffer passes (Buffer A-D).
+///
+/// Every buffer owns two textures that are swapped every frame. A pass reads the current frame of
+/// the buffers rendered before it and the previous frame of itself and all later buffers, so a
+/// buffer sampling itself sees its own output of the last frame. The main shader sees the current
+/// frame of every buffer. All textures are bound in group 1:
+///
+/// ```glsl
+/// layout (set = 1, binding = 0) uniform texture2D buffer_a;
+/// layout (set = 1, binding = 4) uniform sampler buffer_sampler;
+/// vec4 previous = texture(sampler2D(buffer_a, buffer_sampler), uv);
+/// ```
+pub struct BufferPasses {
+    pub pipelines: Vec<RenderPipeline>,
+    pub textures: Vec<[Texture; 2]>,
+    pub layout: BindGroupLayout,
+    /// indexed by pass (buffers followed by the main shader), then by frame parity
+    pub bind_groups: Vec<[BindGroup; 2]>,
+    pub vertices: Buffer,
+}
+
+pub fn buffer_pass_layout(device: &Device, buffers: usize) -> BindGroupLayout {
+    let mut entries: Vec<_> = (0..buffers as u32)
+        .map(|binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        })
+        .collect();
+    entries.push(wgpu::BindGroupLayoutEntry {
+        binding: SAMPLER_BINDING,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        count: None,
+    });
+    device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some("buffer passes"),
+        entries: &entries,
+    })
+}
+
+impl BufferPasses {
+    pub fn new(
+        device: &Device,
+        vertex_shader: &ShaderModule,
+        fragment_shaders: &[ShaderModule],
+        uniform_layout: &BindGroupLayout,
+        layout: BindGroupLayout,
+        size: WindowSize,
+    ) -> BufferPasses {
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("buffer pipeline layout"),
+                bind_group_layouts: &[uniform_layout, &layout],
+                push_constant_ranges: &[],
+            });
+        let pipelines = fragment_shaders
+            .iter()
+            .map(|fragment_shader| {
+                device.create_render_pipeline(&render_pipeline::render_pipeline_descriptor(
+                    vertex_shader,
+                    fragment_shader,
+                    &render_pipeline_layout,
+                    &[Some(wgpu::ColorTargetState {
+                        format: BUFFER_FORMAT,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    &[Vertex::desc()],
+                ))
+            })
+            .collect();
+
+        let vertices = Vertex::rect(Vector2 { x: 0.0, y: 0.0 }, 2.0, 2.0, 0.0);
+        let mut vertex_bytes = vec![];
+        let mut vertex_bytes_writer = crevice::std430::Writer::new(&mut vertex_bytes);
+        vertex_bytes_writer
+            .write_iter(vertices.iter().cloned())
+            .unwrap();
+        let vertices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("buffer pass vertex buffer"),
+            contents: &vertex_bytes[..],
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let mut passes = BufferPasses {
+            pipelines,
+            textures: Vec::new(),
+            layout,
+            bind_groups: Vec::new(),
+            vertices,
+        };
+        passes.resize(device, size);
+        passes
+    }
+
+    /// Recreates the buffer textures with the new size, their content is lost.
+    pub fn resize(&mut self, device: &Device, size: WindowSize) {
+        self.textures = (0..self.pipelines.len())
+            .map(|_| [create_texture(device, size), create_texture(device, size)])
+            .collect();
+        let views: Vec<[TextureView; 2]> = self
+            .textures
+            .iter()
+            .map(|[a, b]| {
+                [
+                    a.create_view(&Default::default()),
+                    b.create_view(&Default::default()),
+                ]
+            })
+            .collect();
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("buffer sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        self.bind_groups = (0..=self.pipelines.len())
+            .map(|pass| {
+                [0, 1].map(|parity| {
+                    let mut entries: Vec<_> = views
+                        .iter()
+                        .enumerate()
+                        .map(|(buffer, v)| wgpu::BindGroupEntry {
+                            binding: buffer as u32,
+                            resource: wgpu::BindingResource::TextureView(if buffer < pass {
+                                &v[parity]
+                            } else {
+                                &v[1 - parity]
+                            }),
+                        })
+                        .collect();
+                    entries.push(wgpu::BindGroupEntry {
+                        binding: SAMPLER_BINDING,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    });
+                    device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some("buffer pass bind group"),
+                        layout: &self.layout,
+                        entries: &entries,
+                    })
+                })
+            })
+            .collect();
+    }
+
+    /// Renders all buffer passes of the frame with the given parity.
+    pub fn encode(&self, encoder: &mut CommandEncoder, uniforms: &BindGroup, parity: usize) {
+        for (i, pipeline) in self.pipelines.iter().enumerate() {
+            let view = self.textures[i][parity].create_view(&Default::default());
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Buffer Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_vertex_buffer(0, self.vertices.slice(..));
+            render_pass.set_bind_group(0, uniforms, &[]);
+            render_pass.set_bind_group(1, &self.bind_groups[i][parity], &[]);
+            render_pass.draw(0..6, 0..1);
+        }
+    }
+
+    /// Bind group for the main shader, it samples the current frame of every buffer.
+    pub fn main_bind_group(&self, parity: usize) -> &BindGroup {
+        &self.bind_groups[self.pipelines.len()][parity]
+    }
+}
+
+fn create_texture(device: &Device, size: WindowSize) -> Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("buffer pass texture"),
+        size: wgpu::Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: BUFFER_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    })
+}
@@ -0,0 +1,390 @@
+use std::path::{Path, PathBuf};
+
+#[cfg(all(unix, not(target_family = "wasm")))]
+use std::time::Instant;
+#[cfg(target_family = "wasm")]
+use web_time::Instant;
+
+use mint::Vector2;
+use wgpu::{
+    util::DeviceExt, BindGroup, BindGroupLayout, BindGroupLayoutDescriptor, Buffer, Device, Queue,
+    RenderPass, RenderPipeline, Sampler, TextureFormat, TextureView,
+};
+
+use crate::{
+    model::{create_uniform_buffer, Vertex},
+    render_pipeline,
+    renderable::MainDisplay,
+    shader::BUFFER_NAMES,
+    state::WindowSize,
+};
+
+/// How the shaders of a playlist are switched.
+#[derive(Clone, Copy, Debug)]
+pub struct PlaylistOptions {
+    /// seconds each shader is shown, `None` to only switch with the keyboard
+    pub interval: Option<f32>,
+    /// seconds the outgoing shader takes to fade out over the incoming one
+    pub crossfade: f32,
+}
+
+/// Shader files of a playlist. A directory contains every `.frag` and `.wgsl` file in it
+/// except buffer passes, in alphabetical order. A playlist file lists one shader per line
+/// relative to the file, empty lines and lines starting with `#` are skipped.
+#[allow(dead_code)]
+pub fn playlist_entries(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let entries = if path.is_dir() {
+        let mut entries: Vec<_> = std::fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<_, _>>()?;
+        entries.retain(|p| {
+            let name = p.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            let is_shader = name.ends_with(".frag") || name.ends_with(".wgsl");
+            let is_pass = BUFFER_NAMES
+                .iter()
+                .any(|pass| name.contains(&format!(".{pass}.")));
+            p.is_file() && is_shader && !is_pass
+        });
+        entries.sort();
+        entries
+    } else {
+        let dir = path.parent().unwrap_or(Path::new(""));
+        std::fs::read_to_string(path)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| dir.join(line))
+            .collect()
+    };
+    if entries.is_empty() {
+        return Err(std::io::Error::other(format!(
+            "{}: no shaders in playlist",
+            path.display()
+        )));
+    }
+    Ok(entries)
+}
+
+/// Displays of all playlist entries. The current one is moved out to the `State`, its slot is
+/// empty while it is shown or fading out.
+pub struct Playlist {
+    displays: Vec<Option<MainDisplay>>,
+    pub current: usize,
+    pub options: PlaylistOptions,
+    shown_since: Instant,
+}
+
+impl Playlist {
+    /// `displays` are all entries except the current one at index 0.
+    pub fn new(displays: Vec<MainDisplay>, options: PlaylistOptions) -> Playlist {
+        Playlist {
+            displays: std::iter::once(None)
+                .chain(displays.into_iter().map(Some))
+                .collect(),
+            current: 0,
+            options,
+            shown_since: Instant::now(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.displays.len()
+    }
+
+    /// Entry `step` places after the current one, wrapping around.
+    pub fn offset(&self, step: isize) -> usize {
+        (self.current as isize + step).rem_euclid(self.len() as isize) as usize
+    }
+
+    /// True once the current shader was shown for the playlist interval.
+    pub fn due(&self) -> bool {
+        self.len() > 1
+            && self
+                .options
+                .interval
+                .is_some_and(|interval| self.shown_since.elapsed().as_secs_f32() >= interval)
+    }
+
+    /// Takes the display of `index` out to be shown, restarting its clock.
+    pub fn take(&mut self, index: usize) -> MainDisplay {
+        let mut display = self.displays[index].take().unwrap();
+        display.reset_time();
+        self.current = index;
+        self.shown_since = Instant::now();
+        display
+    }
+
+    /// Returns a display that is no longer shown.
+    pub fn put_back(&mut self, index: usize, display: MainDisplay) {
+        self.displays[index] = Some(display);
+    }
+}
+
+/// Opacity of the outgoing shader `elapsed` seconds into a crossfade of `duration` seconds,
+/// eased so the blend starts and ends smoothly.
+pub fn fade_opacity(elapsed: f32, duration: f32) -> f32 {
+    let t = (elapsed / duration).clamp(0.0, 1.0);
+    1.0 - t * t * (3.0 - 2.0 * t)
+}
+
+struct Target {
+    size: WindowSize,
+    view: TextureView,
+    bind_group: BindGroup,
+}
+
+/// The shader fading out, rendered into its own target and drawn over the incoming shader.
+pub struct Outgoing {
+    pub index: usize,
+    pub display: MainDisplay,
+    start: Instant,
+}
+
+/// Blends the outgoing shader of a playlist switch over the incoming one.
+pub struct Crossfade {
+    pub duration: f32,
+    pub outgoing: Option<Outgoing>,
+    pipeline: RenderPipeline,
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    vertices: Buffer,
+    opacity: Buffer,
+    format: TextureFormat,
+    target: Option<Target>,
+}
+
+impl Crossfade {
+    pub fn new(device: &Device, format: TextureFormat, duration: f32) -> Crossfade {
+        let vertex_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("vertex_shader"),
+            source: wgpu::ShaderSource::Glsl {
+                shader: include_str!("shader.vert").into(),
+                stage: wgpu::naga::ShaderStage::Vertex,
+                defines: wgpu::naga::FastHashMap::default(),
+            },
+        });
+        let fragment_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("fragment_shader"),
+            source: wgpu::ShaderSource::Glsl {
+                shader: include_str!("crossfade.frag").into(),
+                stage: wgpu::naga::ShaderStage::Fragment,
+                defines: wgpu::naga::FastHashMap::default(),
+            },
+        });
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("crossfade"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("crossfade pipeline layout"),
+                bind_group_layouts: &[&layout],
+                push_constant_ranges: &[],
+            });
+        let pipeline = device.create_render_pipeline(&render_pipeline::render_pipeline_descriptor(
+            &vertex_shader,
+            &fragment_shader,
+            &render_pipeline_layout,
+            &[Some(wgpu::ColorTargetState {
+                format,
+                blend: render_pipeline::blend_state(format, wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::COLOR,
+            })],
+            &[Vertex::desc()],
+        ));
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("crossfade sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let vertices = Vertex::rect(Vector2 { x: 0.0, y: 0.0 }, 2.0, 2.0, 0.0);
+        let mut vertex_bytes = vec![];
+        let mut vertex_bytes_writer = crevice::std430::Writer::new(&mut vertex_bytes);
+        vertex_bytes_writer
+            .write_iter(vertices.iter().cloned())
+            .unwrap();
+        let vertices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("crossfade vertex buffer"),
+            contents: &vertex_bytes[..],
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let opacity = create_uniform_buffer("crossfade opacity", device, [1.0, 0.0, 0.0, 0.0]);
+        Crossfade {
+            duration,
+            outgoing: None,
+            pipeline,
+            layout,
+            sampler,
+            vertices,
+            opacity,
+            format,
+            target: None,
+        }
+    }
+
+    pub fn start(&mut self, index: usize, display: MainDisplay) {
+        self.outgoing = Some(Outgoing {
+            index,
+            display,
+            start: Instant::now(),
+        });
+    }
+
+    /// Ends the crossfade if it is over or `force` is set, returning the outgoing display.
+    pub fn finish(&mut self, force: bool) -> Option<Outgoing> {
+        let done = self
+            .outgoing
+            .as_ref()
+            .is_some_and(|o| force || o.start.elapsed().as_secs_f32() >= self.duration);
+        if done {
+            self.target = None;
+            self.outgoing.take()
+        } else {
+            None
+        }
+    }
+
+    /// Sizes the target and the outgoing display like the incoming one, advances the outgoing
+    /// clock and uploads the opacity. Called once per frame while a crossfade runs.
+    pub fn prepare(&mut self, device: &Device, queue: &Queue, size: WindowSize) {
+        let Some(outgoing) = &mut self.outgoing else {
+            return;
+        };
+        if outgoing.display.size != size {
+            outgoing.display.resize(device, size);
+        }
+        outgoing.display.advance();
+        let opacity = fade_opacity(outgoing.start.elapsed().as_secs_f32(), self.duration);
+        queue.write_buffer(&self.opacity, 0, &opacity.to_ne_bytes());
+        if self.target.as_ref().is_some_and(|t| t.size == size) {
+            return;
+        }
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("crossfade texture"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&Default::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("crossfade bind group"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.opacity.as_entire_binding(),
+                },
+            ],
+        });
+        self.target = Some(Target {
+            size,
+            view,
+            bind_group,
+        });
+    }
+
+    /// The outgoing display and the view it renders into while a crossfade runs.
+    pub fn outgoing(&self) -> Option<(&MainDisplay, &TextureView)> {
+        match (&self.outgoing, &self.target) {
+            (Some(outgoing), Some(target)) => Some((&outgoing.display, &target.view)),
+            _ => None,
+        }
+    }
+
+    /// Draws the outgoing shader over the incoming one.
+    pub fn render<'a, 'b: 'a>(&'b self, render_pass: &mut RenderPass<'a>) {
+        if let (Some(_), Some(target)) = (&self.outgoing, &self.target) {
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_vertex_buffer(0, self.vertices.slice(..));
+            render_pass.set_bind_group(0, &target.bind_group, &[]);
+            render_pass.draw(0..6, 0..1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{fade_opacity, playlist_entries};
+    use crate::util::TempDir;
+
+    #[test]
+    fn lists_shaders_of_a_directory_or_file() {
+        let dir = TempDir::new("playlist");
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        for file in [
+            "b.frag",
+            "a.wgsl",
+            "a.buffer_a.wgsl",
+            "notes.txt",
+            "lib/sdf.glsl",
+        ] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+        std::fs::write(
+            dir.join("show.playlist"),
+            "# evening\nb.frag\n\n../a.frag\n",
+        )
+        .unwrap();
+        assert_eq!(
+            playlist_entries(&dir).unwrap(),
+            vec![dir.join("a.wgsl"), dir.join("b.frag")]
+        );
+        assert_eq!(
+            playlist_entries(&dir.join("show.playlist")).unwrap(),
+            vec![dir.join("b.frag"), dir.join("../a.frag")]
+        );
+        assert!(playlist_entries(&dir.join("lib")).is_err());
+    }
+
+    #[test]
+    fn fades_out_smoothly() {
+        assert_eq!(fade_opacity(0.0, 2.0), 1.0);
+        assert_eq!(fade_opacity(1.0, 2.0), 0.5);
+        assert_eq!(fade_opacity(3.0, 2.0), 0.0);
+        assert!(fade_opacity(0.2, 2.0) > 0.9);
+    }
+}
@@ -0,0 +1,255 @@
+use std::{
+    cell::Cell,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use wgpu::{Buffer, CommandEncoder, Device, QuerySet, Queue, RenderPassTimestampWrites};
+
+/// Render passes timed with timestamp queries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimedPass {
+    /// the shown shader at the render scale
+    Main = 0,
+    /// the upscaled image drawn to the window, only with a render scale below 1
+    Upscale = 1,
+    /// sliders and error overlay
+    Ui = 2,
+}
+
+const PASSES: usize = 3;
+/// Frames whose timestamps can be on their way back from the GPU at once, frames are not timed
+/// while all are in use.
+const READBACKS: usize = 4;
+
+impl TimedPass {
+    pub const ALL: [TimedPass; PASSES] = [TimedPass::Main, TimedPass::Upscale, TimedPass::Ui];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TimedPass::Main => "main",
+            TimedPass::Upscale => "upscale",
+            TimedPass::Ui => "ui",
+        }
+    }
+
+    /// Offset of the pass in the resolve buffer, resolved queries have to be aligned.
+    fn offset(self) -> u64 {
+        self as u64 * wgpu::QUERY_RESOLVE_BUFFER_ALIGNMENT
+    }
+}
+
+/// Milliseconds per pass, `None` for passes that did not run.
+pub type PassTimes = [Option<f32>; PASSES];
+
+struct Readback {
+    buffer: Buffer,
+    /// frame and the passes that ran in it, `None` while the buffer is free
+    frame: Option<(u64, [bool; PASSES])>,
+    mapped: Arc<AtomicBool>,
+}
+
+/// Measures the GPU time of the render passes with timestamp queries, requires
+/// `wgpu::Features::TIMESTAMP_QUERY`. The results arrive a few frames late without stalling the
+/// GPU, they are averaged until `averages` is called and optionally written to a csv file.
+pub struct GpuProfiler {
+    query_set: QuerySet,
+    resolve: Buffer,
+    readbacks: Vec<Readback>,
+    /// readback of the frame being recorded, `None` if it is not timed
+    active: Option<usize>,
+    used: Cell<[bool; PASSES]>,
+    frame: u64,
+    /// nanoseconds per timestamp tick
+    period: f32,
+    totals: [(f32, u32); PASSES],
+    csv: Option<BufWriter<File>>,
+}
+
+impl GpuProfiler {
+    pub fn new(device: &Device, queue: &Queue, csv: Option<&Path>) -> std::io::Result<Self> {
+        let csv = match csv {
+            Some(path) => {
+                let mut csv = BufWriter::new(File::create(path)?);
+                let names: Vec<_> = TimedPass::ALL.iter().map(|p| p.name()).collect();
+                writeln!(csv, "frame,{}", names.join(","))?;
+                Some(csv)
+            }
+            None => None,
+        };
+        let size = PASSES as u64 * wgpu::QUERY_RESOLVE_BUFFER_ALIGNMENT;
+        let readbacks = (0..READBACKS)
+            .map(|_| Readback {
+                buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("timestamp readback"),
+                    size,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                }),
+                frame: None,
+                mapped: Arc::new(AtomicBool::new(false)),
+            })
+            .collect();
+        Ok(GpuProfiler {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("pass timestamps"),
+                ty: wgpu::QueryType::Timestamp,
+                count: 2 * PASSES as u32,
+            }),
+            resolve: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("timestamp resolve"),
+                size,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readbacks,
+            active: None,
+            used: Cell::new([false; PASSES]),
+            frame: 0,
+            period: queue.get_timestamp_period(),
+            totals: [(0.0, 0); PASSES],
+            csv,
+        })
+    }
+
+    /// Collects the finished measurements and starts timing a new frame if a readback buffer is
+    /// free.
+    pub fn begin_frame(&mut self, device: &Device) {
+        device.poll(wgpu::Maintain::Poll);
+        for i in 0..self.readbacks.len() {
+            if self.readbacks[i].mapped.swap(false, Ordering::Acquire) {
+                self.collect(i);
+            }
+        }
+        self.active = self.readbacks.iter().position(|r| r.frame.is_none());
+        self.used.set([false; PASSES]);
+    }
+
+    /// Timestamp writes for the descriptor of `pass`, `None` if the frame is not timed.
+    pub fn timestamp_writes(&self, pass: TimedPass) -> Option<RenderPassTimestampWrites<'_>> {
+        self.active?;
+        let mut used = self.used.get();
+        used[pass as usize] = true;
+        self.used.set(used);
+        Some(RenderPassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(2 * pass as u32),
+            end_of_pass_write_index: Some(2 * pass as u32 + 1),
+        })
+    }
+
+    /// Copies the timestamps of the frame into its readback buffer, has to be the last command
+    /// of the frame.
+    pub fn resolve(&self, encoder: &mut CommandEncoder) {
+        let Some(active) = self.active else {
+            return;
+        };
+        let used = self.used.get();
+        for pass in TimedPass::ALL.into_iter().filter(|p| used[*p as usize]) {
+            let first = 2 * pass as u32;
+            encoder.resolve_query_set(
+                &self.query_set,
+                first..first + 2,
+                &self.resolve,
+                pass.offset(),
+            );
+        }
+        let buffer = &self.readbacks[active].buffer;
+        encoder.copy_buffer_to_buffer(&self.resolve, 0, buffer, 0, buffer.size());
+    }
+
+    /// Maps the readback buffer of the frame once the submitted work is done.
+    pub fn end_frame(&mut self) {
+        if let Some(active) = self.active.take() {
+            let readback = &mut self.readbacks[active];
+            readback.frame = Some((self.frame, self.used.get()));
+            let mapped = readback.mapped.clone();
+            readback
+                .buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    if result.is_ok() {
+                        mapped.store(true, Ordering::Release);
+                    }
+                });
+        }
+        self.frame += 1;
+    }
+
+    fn collect(&mut self, index: usize) {
+        let readback = &mut self.readbacks[index];
+        let Some((frame, used)) = readback.frame.take() else {
+            return;
+        };
+        let mut times: PassTimes = [None; PASSES];
+        {
+            let data = readback.buffer.slice(..).get_mapped_range();
+            let timestamp = |offset: u64| {
+                let offset = offset as usize;
+                u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+            };
+            for pass in TimedPass::ALL.into_iter().filter(|p| used[*p as usize]) {
+                let ticks = timestamp(pass.offset() + 8).saturating_sub(timestamp(pass.offset()));
+                times[pass as usize] = Some(ticks as f32 * self.period / 1_000_000.0);
+            }
+        }
+        readback.buffer.unmap();
+        for (total, time) in self.totals.iter_mut().zip(times) {
+            if let Some(time) = time {
+                total.0 += time;
+                total.1 += 1;
+            }
+        }
+        if let Some(csv) = &mut self.csv {
+            let columns: Vec<_> = times
+                .iter()
+                .map(|t| t.map(|t| format!("{t:.4}")).unwrap_or_default())
+                .collect();
+            if let Err(e) = writeln!(csv, "{frame},{}", columns.join(",")) {
+                log::warn!("writing gpu times failed: {e}");
+                self.csv = None;
+            }
+        }
+    }
+
+    /// Average milliseconds per pass since the last call, `None` for passes that did not run.
+    pub fn averages(&mut self) -> PassTimes {
+        let averages = self
+            .totals
+            .map(|(sum, count)| (count > 0).then(|| sum / count as f32));
+        self.totals = [(0.0, 0); PASSES];
+        if let Some(csv) = &mut self.csv {
+            let _ = csv.flush();
+        }
+        averages
+    }
+}
+
+/// `gpu main 3.120 ms, ui 0.041 ms, total 3.161 ms`
+pub fn format_times(times: &PassTimes) -> String {
+    let passes: Vec<_> = TimedPass::ALL
+        .iter()
+        .zip(times)
+        .filter_map(|(pass, time)| time.map(|time| format!("{} {time:.3} ms", pass.name())))
+        .collect();
+    let total: f32 = times.iter().flatten().sum();
+    format!("gpu {}, total {total:.3} ms", passes.join(", "))
+}
+
+#[cfg(test)]
+mod test {
+    use super::format_times;
+
+    #[test]
+    fn formats_the_passes_that_ran() {
+        assert_eq!(
+            format_times(&[Some(3.0), None, Some(0.25)]),
+            "gpu main 3.000 ms, ui 0.250 ms, total 3.250 ms"
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use std::{collections::BTreeMap, num::NonZeroU64};
+
+use crevice::std140::AsStd140;
+
+use crate::{channel::CHANNELS, model::Uniforms, multipass::SAMPLER_BINDING};
+
+/// Buffers the host provides in bind group 0. A shader declares the ones it needs with the block
+/// names below at any binding, the bind group is built from the declarations.
+/// The discriminant is the index into `MainDisplay::buffers`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resource {
+    Uniforms = 0,
+    Fft = 1,
+    Sliders = 2,
+    Eyes = 3,
+}
+
+/// Anything a shader can declare in bind group 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Binding {
+    Buffer(Resource),
+    /// `uniform texture2D channel<n>;`
+    Channel(usize),
+    /// `uniform sampler channel<n>_sampler;`
+    ChannelSampler(usize),
+}
+
+/// group 0 binding -> resource
+pub type Bindings = BTreeMap<u32, Binding>;
+
+impl Resource {
+    const ALL: [Resource; 4] = [
+        Resource::Uniforms,
+        Resource::Fft,
+        Resource::Sliders,
+        Resource::Eyes,
+    ];
+
+    pub fn block_name(self) -> &'static str {
+        match self {
+            Resource::Uniforms => "UniformParameters",
+            Resource::Fft => "fftBuffer",
+            Resource::Sliders => "SliderParameters",
+            Resource::Eyes => "eyeBuffer",
+        }
+    }
+
+    fn declaration(self) -> &'static str {
+        match self {
+            Resource::Uniforms => "uniform",
+            _ => "readonly buffer",
+        }
+    }
+
+    fn matches(self, space: naga::AddressSpace) -> bool {
+        match (self, space) {
+            (Resource::Uniforms, naga::AddressSpace::Uniform) => true,
+            (Resource::Uniforms, _) => false,
+            (_, naga::AddressSpace::Storage { access }) => {
+                !access.contains(naga::StorageAccess::STORE)
+            }
+            _ => false,
+        }
+    }
+
+    /// Storage buffers are not available with the webgl backend.
+    fn available(self) -> bool {
+        cfg!(not(target_family = "wasm")) || self == Resource::Uniforms
+    }
+}
+
+impl Binding {
+    /// Buffers are found by their block name, textures and samplers by their variable name.
+    fn find(block_name: &str, variable_name: &str) -> Option<Binding> {
+        if let Some(resource) = Resource::ALL
+            .into_iter()
+            .find(|r| r.block_name() == block_name)
+        {
+            return Some(Binding::Buffer(resource));
+        }
+        (0..CHANNELS).find_map(|i| {
+            if variable_name == format!("channel{i}") {
+                Some(Binding::Channel(i))
+            } else if variable_name == format!("channel{i}_sampler") {
+                Some(Binding::ChannelSampler(i))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn declaration(self) -> &'static str {
+        match self {
+            Binding::Buffer(resource) => resource.declaration(),
+            Binding::Channel(_) => "uniform texture2D",
+            Binding::ChannelSampler(_) => "uniform sampler",
+        }
+    }
+
+    fn matches(self, space: naga::AddressSpace, ty: &naga::TypeInner) -> bool {
+        match self {
+            Binding::Buffer(resource) => resource.matches(space),
+            Binding::Channel(_) => is_texture(ty),
+            Binding::ChannelSampler(_) => is_sampler(ty),
+        }
+    }
+
+    fn available(self) -> bool {
+        match self {
+            Binding::Buffer(resource) => resource.available(),
+            _ => true,
+        }
+    }
+
+    pub fn layout_entry(self, binding: u32) -> wgpu::BindGroupLayoutEntry {
+        let ty = match self {
+            Binding::Buffer(Resource::Uniforms) => wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: NonZeroU64::new(Uniforms::std140_size_static() as u64),
+            },
+            Binding::Buffer(_) => wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            Binding::Channel(_) => wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            Binding::ChannelSampler(_) => {
+                wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering)
+            }
+        };
+        wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty,
+            count: None,
+        }
+    }
+}
+
+/// `uniform texture2D`
+fn is_texture(ty: &naga::TypeInner) -> bool {
+    matches!(
+        ty,
+        naga::TypeInner::Image {
+            dim: naga::ImageDimension::D2,
+            arrayed: false,
+            class: naga::ImageClass::Sampled {
+                kind: naga::ScalarKind::Float,
+                multi: false
+            },
+        }
+    )
+}
+
+/// `uniform sampler`
+fn is_sampler(ty: &naga::TypeInner) -> bool {
+    matches!(ty, naga::TypeInner::Sampler { comparison: false })
+}
+
+/// Checks a declaration outside of group 0 against the group 1 layout of `buffers` buffer passes,
+/// see `BufferPasses`.
+fn check_buffer_binding(
+    name: &str,
+    binding: &naga::ResourceBinding,
+    ty: &naga::TypeInner,
+    buffers: usize,
+) -> Result<(), String> {
+    let (group, index) = (binding.group, binding.binding);
+    if group != 1 {
+        return Err(format!(
+            "{name}: set {group} binding {index} is not bound, only set 0 and 1 are"
+        ));
+    }
+    if buffers == 0 {
+        return Err(format!(
+            "{name}: set 1 binding {index} is not bound, set 1 holds the textures of buffer passes and there are none"
+        ));
+    }
+    if (index as usize) < buffers {
+        let buffer = char::from(b'a' + index as u8);
+        if !is_texture(ty) {
+            return Err(format!(
+                "{name}: set 1 binding {index} is buffer_{buffer} and has to be declared as `uniform texture2D`"
+            ));
+        }
+    } else if index == SAMPLER_BINDING {
+        if !is_sampler(ty) {
+            return Err(format!(
+                "{name}: set 1 binding {index} is the buffer sampler and has to be declared as `uniform sampler`"
+            ));
+        }
+    } else {
+        return Err(format!(
+            "{name}: set 1 binding {index} is not bound, the {buffers} buffer passes use binding 0-{} and the sampler binding {SAMPLER_BINDING}",
+            buffers - 1
+        ));
+    }
+    Ok(())
+}
+
+/// Adds the group 0 resources used by the entry point of `module` to `bindings` and checks the
+/// group 1 declarations against the layout of `buffers` buffer passes. Declarations the entry
+/// point never touches are ignored, like wgpu does.
+pub fn reflect(
+    name: &str,
+    module: &naga::Module,
+    info: &naga::valid::ModuleInfo,
+    buffers: usize,
+    bindings: &mut Bindings,
+) -> Result<(), String> {
+    let entry_point = info.get_entry_point(0);
+    for (handle, variable) in module.global_variables.iter() {
+        let Some(binding) = &variable.binding else {
+            continue;
+        };
+        if entry_point[handle].is_empty() {
+            continue;
+        }
+        let ty = &module.types[variable.ty];
+        if binding.group != 0 {
+            check_buffer_binding(name, binding, &ty.inner, buffers)?;
+            continue;
+        }
+        let block_name = ty.name.as_deref().unwrap_or_default();
+        let variable_name = variable.name.as_deref().unwrap_or_default();
+        let Some(resource) = Binding::find(block_name, variable_name) else {
+            let known: Vec<_> = Resource::ALL.iter().map(|r| r.block_name()).collect();
+            return Err(format!(
+                "{name}: unknown resource `{block_name} {variable_name}` at binding {}, expected one of {}, channel0-{} or channel0-{}_sampler",
+                binding.binding,
+                known.join(", "),
+                CHANNELS - 1,
+                CHANNELS - 1
+            ));
+        };
+        let declared = match resource {
+            Binding::Buffer(resource) => resource.block_name(),
+            _ => variable_name,
+        };
+        if !resource.matches(variable.space, &ty.inner) {
+            return Err(format!(
+                "{name}: `{declared}` at binding {} has to be declared as `{}`",
+                binding.binding,
+                resource.declaration()
+            ));
+        }
+        if !resource.available() {
+            return Err(format!(
+                "{name}: `{declared}` at binding {} is not available on this platform",
+                binding.binding
+            ));
+        }
+        match bindings.insert(binding.binding, resource) {
+            Some(previous) if previous != resource => {
+                return Err(format!(
+                    "{name}: binding {} is `{declared}` but {previous:?} in another pass",
+                    binding.binding
+                ));
+            }
+            _ => (),
+        }
+    }
+    Ok(())
+}
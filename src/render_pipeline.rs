@@ -36,3 +36,16 @@ pub fn render_pipeline_descriptor<'a>(
         multiview: None,
     }
 }
+
+/// `blend` for targets that support blending. Float32 targets can not blend, `REPLACE` is the
+/// same as no blending and the alpha blended overlays never draw into file renders.
+pub fn blend_state(
+    format: wgpu::TextureFormat,
+    blend: wgpu::BlendState,
+) -> Option<wgpu::BlendState> {
+    format
+        .guaranteed_format_features(wgpu::Features::empty())
+        .flags
+        .contains(wgpu::TextureFormatFeatureFlags::BLENDABLE)
+        .then_some(blend)
+}
@@ -1,19 +1,137 @@
 use std::{
+    path::Path,
     sync::{Arc, Mutex},
     time::Duration,
 };
 
-use wgpu::{Backends, InstanceFlags, TextureFormat};
+use wgpu::{Backends, InstanceFlags, Texture, TextureFormat, TextureView};
 
-use crate::state::{State, WindowSize};
+use crate::{
+    camera::Camera,
+    channel::ChannelImage,
+    playlist::PlaylistOptions,
+    shader::Shaders,
+    state::{State, WindowSize},
+    tiles::{crop, stitch, tiles, Tile},
+    util::{downsample, downsample_linear, f16_to_f32},
+};
+
+/// File format of offline renders. `png` renders into an 8 bit srgb texture, `png16` and `exr`
+/// render into an `Rgba16Float` texture so values above 1 survive. `png16` stores srgb encoded
+/// values clamped to 0-1, `exr` the linear values. `exr32` renders into an `Rgba32Float` texture
+/// for full float precision.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    Png,
+    Png16,
+    Exr,
+    Exr32,
+}
+
+impl OutputFormat {
+    /// `exr` for `.exr` paths, `png` otherwise.
+    #[allow(dead_code)]
+    pub fn from_path(path: &Path) -> OutputFormat {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("exr") => OutputFormat::Exr,
+            _ => OutputFormat::Png,
+        }
+    }
+
+    fn texture_format(self) -> TextureFormat {
+        match self {
+            OutputFormat::Png => TextureFormat::Rgba8UnormSrgb,
+            OutputFormat::Png16 | OutputFormat::Exr => TextureFormat::Rgba16Float,
+            OutputFormat::Exr32 => TextureFormat::Rgba32Float,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Png | OutputFormat::Png16 => "png",
+            OutputFormat::Exr | OutputFormat::Exr32 => "exr",
+        }
+    }
+}
+
+/// Channel type of the pixels read back from the file texture.
+trait Sample: Copy + Default {
+    fn decode(bytes: Vec<u8>, format: TextureFormat) -> Vec<Self>;
+    fn downsample(pixels: &[Self], width: u32, height: u32, factor: u32) -> Vec<Self>;
+}
+
+impl Sample for u8 {
+    fn decode(bytes: Vec<u8>, _format: TextureFormat) -> Vec<Self> {
+        bytes
+    }
+
+    fn downsample(pixels: &[Self], width: u32, height: u32, factor: u32) -> Vec<Self> {
+        downsample(pixels, width, height, factor)
+    }
+}
+
+impl Sample for f32 {
+    fn decode(bytes: Vec<u8>, format: TextureFormat) -> Vec<Self> {
+        match format {
+            TextureFormat::Rgba32Float => bytes
+                .chunks(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+            _ => bytes
+                .chunks(2)
+                .map(|b| f16_to_f32(u16::from_le_bytes([b[0], b[1]])))
+                .collect(),
+        }
+    }
+
+    fn downsample(pixels: &[Self], width: u32, height: u32, factor: u32) -> Vec<Self> {
+        downsample_linear(pixels, width, height, factor)
+    }
+}
+
+/// Renders `frames` images starting at the time offset, advancing the clock by `1 / frame_rate`
+/// per frame.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameSequence {
+    pub frames: u32,
+    pub frame_rate: f32,
+}
 
-#[allow(dead_code)]
+#[allow(dead_code, clippy::too_many_arguments)]
 pub async fn render_to_file(
     srgb: bool,
-    fragment_shader: &str,
+    shaders: &Shaders,
+    camera: Camera,
     fft: &Arc<Mutex<Vec<f32>>>,
     time_offset: f32,
-) {
+    size: WindowSize,
+    image_path: &Path,
+    sequence: Option<FrameSequence>,
+    channels: &[Option<ChannelImage>],
+    ssaa: u32,
+    tile_size: Option<u32>,
+    format: OutputFormat,
+) -> Result<(), String> {
+    let tile_size = tile_size.unwrap_or(wgpu::Limits::default().max_texture_dimension_2d / ssaa);
+    let tiles = tiles(size, tile_size);
+    if tiles.len() > 1 && !shaders.buffers.is_empty() {
+        return Err(format!(
+            "buffer passes can not be rendered in tiles, the image needs {} tiles of {tile_size} pixels, use a larger --tile-size",
+            tiles.len()
+        ));
+    }
+    let (Some(width), Some(height)) = (size.width.checked_mul(ssaa), size.height.checked_mul(ssaa))
+    else {
+        return Err(format!(
+            "{}x{} times --ssaa {ssaa} is too large",
+            size.width, size.height
+        ));
+    };
+    let render_size = WindowSize { width, height };
+    let target = WindowSize {
+        width: tile_size.min(size.width) * ssaa,
+        height: tile_size.min(size.height) * ssaa,
+    };
     std::thread::sleep(Duration::from_secs(13));
     let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
         backends: Backends::VULKAN,
@@ -24,18 +142,198 @@ pub async fn render_to_file(
     let (mut state, f, t) = State::new(
         instance,
         None,
-        Some(TextureFormat::Rgba8UnormSrgb),
-        WindowSize {
-            width: 1920,
-            height: 1080,
-        },
-        fragment_shader,
+        Some(format.texture_format()),
+        target,
+        std::slice::from_ref(shaders),
+        &[camera],
         fft,
         time_offset,
         Arc::new(Mutex::new(Vec::new())),
+        channels,
         srgb,
         false,
+        wgpu::PresentMode::Fifo,
+        PlaylistOptions {
+            interval: None,
+            crossfade: 0.0,
+        },
     )
-    .await;
-    state.render(f, t).unwrap();
+    .await?;
+    let (f, t) = (f.unwrap(), t.unwrap());
+    let render = |state: &mut State, path: &Path| match format {
+        OutputFormat::Png => {
+            let image = render_image::<u8>(
+                state,
+                &f,
+                &t,
+                format,
+                size,
+                render_size,
+                target,
+                &tiles,
+                ssaa,
+            )?;
+            save_image(image, size, path)
+        }
+        OutputFormat::Png16 | OutputFormat::Exr | OutputFormat::Exr32 => {
+            let image = render_image::<f32>(
+                state,
+                &f,
+                &t,
+                format,
+                size,
+                render_size,
+                target,
+                &tiles,
+                ssaa,
+            )?;
+            save_hdr_image(image, size, format, path)
+        }
+    };
+    match sequence {
+        Some(sequence) => {
+            std::fs::create_dir_all(image_path)
+                .map_err(|e| format!("{}: {e}", image_path.display()))?;
+            for frame in 0..sequence.frames {
+                state.set_time(time_offset + frame as f32 / sequence.frame_rate);
+                let path = image_path.join(frame_file_name(frame + 1, format));
+                render(&mut state, &path)?;
+                println!("{}/{} {}", frame + 1, sequence.frames, path.display());
+            }
+        }
+        None => {
+            state.set_time(time_offset);
+            render(&mut state, image_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Renders one frame tile by tile at `ssaa` times the resolution and stitches the downsampled
+/// tiles into an rgba image of `size`. `render_size` is `size` times `ssaa`, `target` the size of
+/// the file texture. An image too large to allocate is an error.
+#[allow(clippy::too_many_arguments)]
+fn render_image<T: Sample>(
+    state: &mut State,
+    view: &TextureView,
+    texture: &Texture,
+    format: OutputFormat,
+    size: WindowSize,
+    render_size: WindowSize,
+    target: WindowSize,
+    tiles: &[Tile],
+    ssaa: u32,
+) -> Result<Vec<T>, String> {
+    let render_tiles: Vec<_> = tiles
+        .iter()
+        .map(|tile| Tile {
+            x: tile.x * ssaa,
+            y: tile.y * ssaa,
+            width: tile.width * ssaa,
+            height: tile.height * ssaa,
+        })
+        .collect();
+    let too_large = || format!("{}x{} is too large", size.width, size.height);
+    let len = (size.width as usize)
+        .checked_mul(size.height as usize)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .ok_or_else(too_large)?;
+    let mut image = Vec::new();
+    image.try_reserve_exact(len).map_err(|_| too_large())?;
+    image.resize(len, T::default());
+    let mut done = 0;
+    state.render_tiles(view, texture, render_size, &render_tiles, |tile, pixels| {
+        let pixels = crop(
+            &T::decode(pixels, format.texture_format()),
+            target.width,
+            tile.width,
+            tile.height,
+        );
+        let pixels = if ssaa > 1 {
+            T::downsample(&pixels, tile.width, tile.height, ssaa)
+        } else {
+            pixels
+        };
+        let tile = &tiles[done];
+        stitch(&mut image, size.width, tile, &pixels);
+        done += 1;
+        if tiles.len() > 1 {
+            println!("tile {done}/{}", tiles.len());
+        }
+    });
+    Ok(image)
+}
+
+fn save_image(image: Vec<u8>, size: WindowSize, path: &Path) -> Result<(), String> {
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "wasm32")] {
+            let _ = (image, size, path);
+        } else {
+            image::RgbaImage::from_raw(size.width, size.height, image)
+                .unwrap()
+                .save(path)
+                .map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+fn save_hdr_image(
+    image: Vec<f32>,
+    size: WindowSize,
+    format: OutputFormat,
+    path: &Path,
+) -> Result<(), String> {
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "wasm32")] {
+            let _ = (image, size, format, path);
+        } else {
+            let hdr = image::Rgba32FImage::from_raw(size.width, size.height, image).unwrap();
+            let saved = match format {
+                OutputFormat::Png16 => {
+                    let png: image::ImageBuffer<image::Rgba<u16>, _> =
+                        image::ImageBuffer::from_fn(size.width, size.height, |x, y| {
+                            let p = hdr.get_pixel(x, y).0;
+                            let encode = |c: f32, srgb: bool| {
+                                let c = c.clamp(0.0, 1.0);
+                                let c = if srgb { crate::util::linear_to_srgb(c) } else { c };
+                                (c * 65535.0).round() as u16
+                            };
+                            image::Rgba([
+                                encode(p[0], true),
+                                encode(p[1], true),
+                                encode(p[2], true),
+                                encode(p[3], false),
+                            ])
+                        });
+                    png.save(path)
+                }
+                _ => hdr.save(path),
+            };
+            saved.map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+fn frame_file_name(frame: u32, format: OutputFormat) -> String {
+    format!("frame_{frame:05}.{}", format.extension())
+}
+
+#[cfg(test)]
+mod test {
+    use wgpu::TextureFormat;
+
+    use super::Sample;
+
+    #[test]
+    fn decodes_float_samples() {
+        let half = [0x00, 0x3c, 0x00, 0x40].to_vec();
+        assert_eq!(f32::decode(half, TextureFormat::Rgba16Float), [1.0, 2.0]);
+        let full = [1.5f32, -0.25]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        assert_eq!(f32::decode(full, TextureFormat::Rgba32Float), [1.5, -0.25]);
+    }
 }
@@ -1,31 +1,98 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    path::PathBuf,
     sync::{Arc, Mutex},
 };
 
 use wgpu::{Backends, InstanceFlags};
 use winit::{
-    event::{ElementState, Event, KeyEvent, WindowEvent},
-    event_loop::EventLoop,
-    keyboard::{Key, NamedKey},
+    event::{ElementState, Event, KeyEvent, StartCause, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    keyboard::{Key, KeyCode, NamedKey, PhysicalKey},
     monitor::VideoMode,
     window::{Fullscreen, Window, WindowBuilder},
 };
 
+#[cfg(all(unix, not(target_family = "wasm")))]
+use winit::{dpi::PhysicalSize, monitor::MonitorHandle};
+
+#[cfg(all(unix, not(target_family = "wasm")))]
+use crate::shader::ShaderWatcher;
 use crate::{
+    camera::{camera_path, Camera},
+    channel::ChannelImage,
+    playlist::PlaylistOptions,
+    profiler::format_times,
+    shader::Shaders,
     state::{State, WindowSize},
-    util::Fps,
+    upscale::RenderScale,
+    util::{Fps, FrameLimiter},
 };
 
+/// Frames the GPU times of `--profile` are averaged over.
+const PROFILE_INTERVAL: u64 = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum WindowMode {
+    Windowed,
+    /// fullscreen window at the desktop resolution
+    Borderless,
+    /// fullscreen with the largest video mode of the monitor
+    Exclusive,
+}
+
+/// When rendered frames are shown, see `wgpu::PresentMode`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum PresentMode {
+    /// wait for vsync, frames are never dropped
+    Fifo,
+    /// wait for vsync, newer frames replace queued ones
+    Mailbox,
+    /// no vsync, may tear
+    Immediate,
+}
+
+impl From<PresentMode> for wgpu::PresentMode {
+    fn from(mode: PresentMode) -> Self {
+        match mode {
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+        }
+    }
+}
+
+/// How the window is opened, ignored on wasm where the canvas is used.
+#[derive(Clone, Copy, Debug)]
+pub struct WindowOptions {
+    pub mode: WindowMode,
+    /// inner size in windowed mode
+    pub size: WindowSize,
+    /// index into the available monitors
+    pub monitor: usize,
+    pub present_mode: PresentMode,
+    /// redraws per second, unlimited if `None`
+    pub max_fps: Option<f32>,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn render_to_screen(
     show_fps: bool,
     pi: bool,
     srgb: bool,
-    fragment_shader: &str,
+    shaders: &[Shaders],
+    cameras: &[Camera],
     fft: &Arc<Mutex<Vec<f32>>>,
     time_offset: f32,
     eye_positions: Arc<Mutex<Vec<[f32; 2]>>>,
-) {
+    channels: &[Option<ChannelImage>],
+    render_scale: RenderScale,
+    window_options: WindowOptions,
+    playlist: PlaylistOptions,
+    shader_paths: &[PathBuf],
+    camera_file: Option<PathBuf>,
+    profile: Option<Option<PathBuf>>,
+) -> Result<(), String> {
     let event_loop = EventLoop::new().unwrap();
 
     cfg_if::cfg_if! {
@@ -49,29 +116,49 @@ pub fn render_to_screen(
                 .expect("couldn't append canvas to document body");
             let window = Arc::new(window);
         } else {
-            let window = {
-                let mut video_modes: Vec<_> = event_loop
-                    .available_monitors()
-                    .next()
-                    .unwrap()
-                    .video_modes()
-                    .collect();
-                video_modes.sort_by_key(|a| a.size().height * a.size().width);
-                let window_mode = video_modes.into_iter().last().unwrap().clone();
-                Arc::new(
-                    WindowBuilder::new()
-                        .with_fullscreen(Some(Fullscreen::Exclusive(window_mode.clone())))
-                        .build(&event_loop)
-                        .unwrap(),
-                )
+            let monitors: Vec<_> = event_loop.available_monitors().collect();
+            let Some(monitor) = monitors.get(window_options.monitor).cloned() else {
+                return Err(format!(
+                    "--monitor {} not found, there are {} monitors",
+                    window_options.monitor,
+                    monitors.len()
+                ));
+            };
+            let window = Arc::new(
+                WindowBuilder::new()
+                    .with_title("sdf_experiment")
+                    .with_inner_size(PhysicalSize::new(
+                        window_options.size.width,
+                        window_options.size.height,
+                    ))
+                    .with_position(monitor.position())
+                    .with_fullscreen(fullscreen(window_options.mode, &monitor))
+                    .build(&event_loop)
+                    .unwrap(),
+            );
+            // F11 switches between a window and this fullscreen mode
+            let fullscreen_mode = match window_options.mode {
+                WindowMode::Windowed => WindowMode::Borderless,
+                mode => mode,
             };
         }
     }
 
+    #[cfg(all(unix, not(target_family = "wasm")))]
+    let mut watchers: Vec<_> = shader_paths
+        .iter()
+        .zip(shaders)
+        .map(|(path, shaders)| ShaderWatcher::new(path, shaders))
+        .collect();
+    #[cfg(target_family = "wasm")]
+    let _ = shader_paths;
+
     let mut fps = Fps::new();
+    let mut limiter = FrameLimiter::new(window_options.max_fps);
     let mut input_state = InputState {
         mouse_position: (0.0, 0.0),
         pressed: HashMap::new(),
+        held: HashSet::new(),
         is_clicked: false,
     };
     log::warn!("before instance");
@@ -92,17 +179,30 @@ pub fn render_to_screen(
         surface,
         None,
         WindowSize { width, height },
-        fragment_shader,
+        shaders,
+        cameras,
         fft,
         time_offset,
         eye_positions,
+        channels,
         srgb,
         pi,
-    ));
+        window_options.present_mode.into(),
+        playlist,
+    ))?;
+    render_state.set_render_scale(render_scale);
+    if let Some(csv) = &profile {
+        if let Err(e) = render_state.enable_profiling(csv.as_deref()) {
+            eprintln!("{}: {e}", csv.as_ref().unwrap().display());
+            std::process::exit(1);
+        }
+    }
+    let mut frames = 0u64;
 
     log::warn!("after create");
     event_loop
         .run(move |event, elwt| match event {
+            Event::NewEvents(StartCause::ResumeTimeReached { .. }) => window.request_redraw(),
             Event::WindowEvent {
                 ref event,
                 window_id,
@@ -115,24 +215,77 @@ pub fn render_to_screen(
                         },
                     ..
                 } => elwt.exit(),
-                WindowEvent::Resized(size) => render_state.resize(*size),
+                #[cfg(all(unix, not(target_family = "wasm")))]
+                WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            logical_key: Key::Named(NamedKey::F11),
+                            state: ElementState::Pressed,
+                            repeat: false,
+                            ..
+                        },
+                    ..
+                } => {
+                    if window.fullscreen().is_some() {
+                        window.set_fullscreen(None);
+                    } else if let Some(monitor) = window.current_monitor() {
+                        window.set_fullscreen(fullscreen(fullscreen_mode, &monitor));
+                    }
+                }
+                WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            logical_key: Key::Named(NamedKey::F5),
+                            state: ElementState::Pressed,
+                            repeat: false,
+                            ..
+                        },
+                    ..
+                } => {
+                    let path = camera_file.clone().or_else(|| {
+                        shader_paths
+                            .get(render_state.current_shader())
+                            .map(|path| camera_path(path))
+                    });
+                    if let Some(path) = path {
+                        match render_state.camera().save(&path) {
+                            Ok(()) => log::warn!("saved camera to {}", path.display()),
+                            Err(e) => eprintln!("{}: {e}", path.display()),
+                        }
+                    }
+                }
+                WindowEvent::Resized(size) => {
+                    render_state.resize(*size);
+                    window.request_redraw();
+                }
                 // WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
                 //     state.resize(**new_inner_size)
                 // }
                 WindowEvent::KeyboardInput {
                     event:
                         KeyEvent {
-                            logical_key, state, ..
+                            logical_key,
+                            physical_key,
+                            state,
+                            ..
                         },
                     ..
                 } => match state {
                     ElementState::Pressed => {
+                        if let PhysicalKey::Code(code) = physical_key {
+                            input_state.held.insert(*code);
+                        }
                         let just_pressed = input_state.is_just_pressed(logical_key.clone());
                         if just_pressed {
                             render_state.report_just_pressed(logical_key.clone());
                         }
                     }
-                    ElementState::Released => input_state.released(logical_key.clone()),
+                    ElementState::Released => {
+                        if let PhysicalKey::Code(code) = physical_key {
+                            input_state.held.remove(code);
+                        }
+                        input_state.released(logical_key.clone())
+                    }
                 },
                 WindowEvent::MouseInput {
                     state: click_state, ..
@@ -141,25 +294,77 @@ pub fn render_to_screen(
                         ElementState::Pressed => input_state.is_clicked = true,
                         ElementState::Released => input_state.is_clicked = false,
                     };
+                    render_state.report_mouse(input_state.mouse_position, input_state.is_clicked);
                 }
                 WindowEvent::CursorMoved { position, .. } => {
+                    if input_state.is_clicked {
+                        render_state.drag_camera((
+                            position.x - input_state.mouse_position.0,
+                            position.y - input_state.mouse_position.1,
+                        ));
+                    }
                     input_state.mouse_position = (position.x, position.y);
+                    render_state.report_mouse(input_state.mouse_position, input_state.is_clicked);
                 }
                 WindowEvent::RedrawRequested => {
+                    let size = window.inner_size();
+                    if size.width == 0 || size.height == 0 {
+                        // minimized, the next resize requests a redraw
+                        return;
+                    }
+                    #[cfg(all(unix, not(target_family = "wasm")))]
+                    if let Some(watcher) = watchers.get_mut(render_state.current_shader()) {
+                        if watcher.changed() {
+                            let loaded = Shaders::load(watcher.path(), watcher.options())
+                                .map_err(|e| e.to_string())
+                                .inspect(|shaders| watcher.watch(shaders));
+                            let reloaded = render_state.reload(loaded);
+                            match reloaded {
+                                Ok(()) => {
+                                    log::warn!("reloaded {}", watcher.path().display());
+                                    window.set_title("sdf_experiment");
+                                }
+                                Err(e) => {
+                                    eprintln!("{e}");
+                                    window.set_title(e.lines().next().unwrap_or_default());
+                                }
+                            }
+                        }
+                    }
+                    fps.presented();
+                    render_state.move_camera(input_state.camera_movement(), fps.frame_time());
+                    render_state.report_fps(fps.fps());
                     if show_fps {
-                        fps.presented();
                         dbg!(fps.fps());
                     }
+                    frames += 1;
+                    if frames.is_multiple_of(PROFILE_INTERVAL) {
+                        if let Some(times) = render_state.gpu_times() {
+                            println!(
+                                "{:.1} fps, frame {:.3} ms, {}",
+                                fps.fps(),
+                                1000.0 / fps.fps(),
+                                format_times(&times)
+                            );
+                        }
+                    }
                     if input_state.is_clicked {
                         // #[cfg(all(unix, not(target_family = "wasm")))]
                         // render_state.report_click(input_state.relative_mouse(&window_mode));
                     }
                     match render_state.render(None, None) {
-                        Ok(_) => window.request_redraw(),
-                        // Err(wgpu::SurfaceError::Lost) => state.resize(todo!()),
+                        Ok(_) => match limiter.next_frame() {
+                            Some(next) => elwt.set_control_flow(ControlFlow::WaitUntil(next)),
+                            None => window.request_redraw(),
+                        },
+                        Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                            render_state.resize(size);
+                            window.request_redraw();
+                        }
                         Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
                         Err(e) => {
                             eprintln!("{e:?}");
+                            window.request_redraw();
                         }
                     }
                 }
@@ -168,12 +373,15 @@ pub fn render_to_screen(
             _ => {}
         })
         .unwrap();
+    Ok(())
 }
 
 #[derive(Debug)]
 struct InputState {
     mouse_position: (f64, f64),
     pressed: HashMap<Key, bool>,
+    /// keys held down by their position on the keyboard
+    held: HashSet<KeyCode>,
     is_clicked: bool,
 }
 
@@ -187,6 +395,23 @@ impl InputState {
         )
     }
 
+    /// (right, up, forward) from the held WASD, Q and E keys by position, faster while shift is
+    /// held.
+    fn camera_movement(&self) -> [f32; 3] {
+        let held = |code| self.held.contains(&code) as i32 as f32;
+        let axis = |positive, negative| held(positive) - held(negative);
+        let speed = if held(KeyCode::ShiftLeft) + held(KeyCode::ShiftRight) > 0.0 {
+            4.0
+        } else {
+            1.0
+        };
+        [
+            axis(KeyCode::KeyD, KeyCode::KeyA) * speed,
+            axis(KeyCode::KeyE, KeyCode::KeyQ) * speed,
+            axis(KeyCode::KeyW, KeyCode::KeyS) * speed,
+        ]
+    }
+
     fn is_just_pressed(&mut self, key: Key) -> bool {
         let previous = self.pressed.get(&key).cloned().unwrap_or(false);
         *self.pressed.entry(key).or_insert(true) = true;
@@ -198,6 +423,18 @@ impl InputState {
     }
 }
 
+#[cfg(all(unix, not(target_family = "wasm")))]
+fn fullscreen(mode: WindowMode, monitor: &MonitorHandle) -> Option<Fullscreen> {
+    match mode {
+        WindowMode::Windowed => None,
+        WindowMode::Borderless => Some(Fullscreen::Borderless(Some(monitor.clone()))),
+        WindowMode::Exclusive => monitor
+            .video_modes()
+            .max_by_key(|m| m.size().width * m.size().height)
+            .map(Fullscreen::Exclusive),
+    }
+}
+
 #[cfg(all(unix, not(target_family = "wasm")))]
 fn screen_size(window: Arc<Window>) -> (u32, u32) {
     let s = window.inner_size();
@@ -1,95 +1,83 @@
 use log::warn;
-#[cfg(all(unix, not(target_family = "wasm")))]
-use std::time::Instant;
-use std::{
-    num::NonZeroU64,
-    sync::{Arc, Mutex},
-};
-#[cfg(target_family = "wasm")]
-use web_time::Instant;
+use std::sync::{Arc, Mutex};
 
+use crevice::std140::AsStd140;
 use mint::Vector2;
 use wgpu::{
     util::DeviceExt, BindGroup, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor, Buffer,
-    Device, Queue, RenderPass, RenderPipeline, TextureFormat,
+    CommandEncoder, Device, Queue, RenderPass, RenderPipeline, Texture, TextureFormat,
 };
 
 use crate::{
-    model::{create_float_vec2_vec_buffer, create_float_vec_buffer, create_uniform_buffer, Vertex},
+    camera::Camera,
+    channel::Channels,
+    clock::Clock,
+    font,
+    model::{
+        create_float_vec2_vec_buffer, create_float_vec_buffer, create_uniform_buffer,
+        create_uniforms_buffer, Uniforms, Vertex,
+    },
+    multipass::{buffer_pass_layout, BufferPasses},
+    reflection::{Binding, Resource},
     render_pipeline,
+    shader::{Fragment, Language, Shaders},
+    state::WindowSize,
+    util::date,
 };
 
 pub struct MainDisplay {
     pub pipeline: RenderPipeline,
-    pub time_start: Instant,
+    /// shader time, `time_offset` is where it starts and `reset_time` returns to
+    pub clock: Clock,
     pub time_offset: f32,
+    /// time and delta of the frame being rendered, updated by `advance`
+    pub current_time: f32,
+    pub time_delta: f32,
+    pub mouse: [f32; 4],
+    pub camera: Camera,
     pub fft: Arc<Mutex<Vec<f32>>>,
     pub eye_positions: Arc<Mutex<Vec<[f32; 2]>>>,
+    pub channels: Arc<Channels>,
     pub vertices: Buffer,
     pub buffers: Vec<Buffer>,
     pub bind_group: BindGroup,
     pub _layout: Vec<wgpu::BindGroupLayoutEntry>,
+    pub passes: Option<BufferPasses>,
+    pub frame: u64,
+    pub format: TextureFormat,
+    pub pi: bool,
+    pub size: WindowSize,
 }
 
-#[cfg(all(unix, not(target_family = "wasm")))]
-fn buffer_layouts() -> Vec<wgpu::BindGroupLayoutEntry> {
-    vec![
-        wgpu::BindGroupLayoutEntry {
-            binding: 0,
-            visibility: wgpu::ShaderStages::FRAGMENT,
-            ty: wgpu::BindingType::Buffer {
-                ty: wgpu::BufferBindingType::Uniform {},
-                has_dynamic_offset: false,
-                min_binding_size: NonZeroU64::new(16),
-            },
-            count: None,
-        },
-        wgpu::BindGroupLayoutEntry {
-            binding: 1,
-            visibility: wgpu::ShaderStages::FRAGMENT,
-            ty: wgpu::BindingType::Buffer {
-                ty: wgpu::BufferBindingType::Storage { read_only: true },
-                has_dynamic_offset: false,
-                min_binding_size: None,
-            },
-            count: None,
-        },
-        wgpu::BindGroupLayoutEntry {
-            binding: 2,
-            visibility: wgpu::ShaderStages::FRAGMENT,
-            ty: wgpu::BindingType::Buffer {
-                ty: wgpu::BufferBindingType::Storage { read_only: true },
-                has_dynamic_offset: false,
-                min_binding_size: None,
-            },
-            count: None,
-        },
-    ]
-}
-#[cfg(target_family = "wasm")]
-fn buffer_layouts() -> Vec<wgpu::BindGroupLayoutEntry> {
-    vec![wgpu::BindGroupLayoutEntry {
-        binding: 0,
-        visibility: wgpu::ShaderStages::FRAGMENT,
-        ty: wgpu::BindingType::Buffer {
-            ty: wgpu::BufferBindingType::Uniform {},
-            has_dynamic_offset: false,
-            min_binding_size: NonZeroU64::new(16),
+fn fragment_source(fragment: &Fragment) -> wgpu::ShaderSource<'_> {
+    match fragment.language {
+        Language::Glsl => wgpu::ShaderSource::Glsl {
+            shader: fragment.code.as_str().into(),
+            stage: wgpu::naga::ShaderStage::Fragment,
+            defines: fragment
+                .defines
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
         },
-        count: None,
-    }]
+        Language::Wgsl => wgpu::ShaderSource::Wgsl(fragment.code.as_str().into()),
+    }
 }
 
 impl MainDisplay {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         fft: Arc<Mutex<Vec<f32>>>,
         eye_positions: Arc<Mutex<Vec<[f32; 2]>>>,
+        channels: Arc<Channels>,
         device: &Device,
-        fragment_shader: &str,
+        shaders: &Shaders,
         format: TextureFormat,
         pi: bool,
         time_offset: f32,
-    ) -> MainDisplay {
+        size: WindowSize,
+    ) -> Result<MainDisplay, String> {
+        let bindings = shaders.check()?;
         let vertex_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("vertex_shader"),
             source: wgpu::ShaderSource::Glsl {
@@ -100,36 +88,72 @@ impl MainDisplay {
         });
         let fragment_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("fragment_shader"),
-            source: wgpu::ShaderSource::Glsl {
-                shader: fragment_shader.into(),
-                stage: wgpu::naga::ShaderStage::Fragment,
-                defines: wgpu::naga::FastHashMap::default(),
-            },
+            source: fragment_source(&shaders.main),
         });
-        let layout_entries = buffer_layouts();
+        let buffer_shaders: Vec<_> = shaders
+            .buffers
+            .iter()
+            .map(|buffer| {
+                device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: Some("buffer_shader"),
+                    source: fragment_source(buffer),
+                })
+            })
+            .collect();
+        let layout_entries: Vec<_> = bindings
+            .iter()
+            .map(|(binding, resource)| resource.layout_entry(*binding))
+            .collect();
         let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: None,
             entries: &layout_entries,
         });
 
-        let fft_lock = fft.lock().unwrap();
-        let fft_value = fft_lock[0];
-        drop(fft_lock);
-        let buffers = create_buffers(device, fft_value);
-        let bind_group = create_bind_group(
-            device,
-            &bind_group_layout,
-            &(buffers
+        let fft_len = fft.lock().unwrap().len();
+        let buffers = create_buffers(device, fft_len);
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &bind_group_layout,
+            entries: &bindings
                 .iter()
-                .take(layout_entries.len())
-                .collect::<Vec<_>>()),
-        );
+                .map(|(binding, resource)| BindGroupEntry {
+                    binding: *binding,
+                    resource: match resource {
+                        Binding::Buffer(buffer) => buffers[*buffer as usize].as_entire_binding(),
+                        Binding::Channel(i) => {
+                            wgpu::BindingResource::TextureView(&channels.views[*i])
+                        }
+                        Binding::ChannelSampler(i) => {
+                            wgpu::BindingResource::Sampler(&channels.samplers[*i])
+                        }
+                    },
+                })
+                .collect::<Vec<_>>(),
+        });
+        let pass_layout = if buffer_shaders.is_empty() {
+            None
+        } else {
+            Some(buffer_pass_layout(device, buffer_shaders.len()))
+        };
+        let bind_group_layouts: Vec<_> = std::iter::once(&bind_group_layout)
+            .chain(pass_layout.as_ref())
+            .collect();
         let render_pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("render pipeline layout"),
-                bind_group_layouts: &[&bind_group_layout],
+                bind_group_layouts: &bind_group_layouts,
                 push_constant_ranges: &[],
             });
+        let passes = pass_layout.map(|layout| {
+            BufferPasses::new(
+                device,
+                &vertex_shader,
+                &buffer_shaders,
+                &bind_group_layout,
+                layout,
+                size,
+            )
+        });
         let vertices = if pi {
             Vertex::rect(Vector2 { x: -0.4, y: -0.45 }, 1.3, 1.1, 0.0)
         } else {
@@ -143,7 +167,7 @@ impl MainDisplay {
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("vertex buffer"),
             contents: &vertex_bytes[..],
-            usage: wgpu::BufferUsages::VERTEX,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
         });
         let pipeline = device.create_render_pipeline(&render_pipeline::render_pipeline_descriptor(
             &vertex_shader,
@@ -151,22 +175,73 @@ impl MainDisplay {
             &render_pipeline_layout,
             &[Some(wgpu::ColorTargetState {
                 format,
-                blend: Some(wgpu::BlendState::REPLACE),
+                blend: render_pipeline::blend_state(format, wgpu::BlendState::REPLACE),
                 write_mask: wgpu::ColorWrites::ALL,
             })],
             &[Vertex::desc()],
         ));
         warn!("pipeline created");
-        Self {
+        Ok(Self {
             pipeline,
-            time_start: Instant::now(),
+            clock: Clock::new(time_offset),
             fft,
             vertices: vertex_buffer,
             bind_group,
             time_offset,
+            current_time: time_offset,
+            time_delta: 0.0,
+            mouse: [0.0; 4],
+            camera: Camera::default(),
             eye_positions,
+            channels,
             buffers,
             _layout: layout_entries,
+            passes,
+            frame: 0,
+            format,
+            pi,
+            size,
+        })
+    }
+
+    /// Builds a new display from `shaders` that continues the clock of this one. Compile and
+    /// validation errors are returned instead of panicking, the current display stays untouched in
+    /// that case.
+    pub fn reload(&mut self, device: &Device, shaders: &Shaders) -> Result<(), String> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let display = MainDisplay::new(
+            self.fft.clone(),
+            self.eye_positions.clone(),
+            self.channels.clone(),
+            device,
+            shaders,
+            self.format,
+            self.pi,
+            self.time_offset,
+            self.size,
+        );
+        let validation = pollster::block_on(device.pop_error_scope());
+        let mut display = display?;
+        if let Some(error) = validation {
+            return Err(error.to_string());
+        }
+        display.clock = self.clock;
+        display.current_time = self.current_time;
+        display.frame = self.frame;
+        display.mouse = self.mouse;
+        display.camera = self.camera;
+        *self = display;
+        Ok(())
+    }
+
+    fn parity(&self) -> usize {
+        (self.frame % 2) as usize
+    }
+
+    /// Renders the buffer passes into their offscreen textures, has to happen before `render`.
+    pub fn render_buffers(&self, encoder: &mut CommandEncoder) {
+        if let Some(passes) = &self.passes {
+            passes.encode(encoder, &self.bind_group, self.parity());
         }
     }
 
@@ -174,33 +249,136 @@ impl MainDisplay {
         render_pass.set_pipeline(&self.pipeline);
         render_pass.set_vertex_buffer(0, self.vertices.slice(..));
         render_pass.set_bind_group(0, &self.bind_group, &[]);
+        if let Some(passes) = &self.passes {
+            render_pass.set_bind_group(1, passes.main_bind_group(self.parity()), &[]);
+        }
         render_pass.draw(0..6, 0..1);
     }
 
-    pub fn update_buffers(&self, queue: &Queue, ui: &Option<UIElements>) {
+    pub fn time(&self) -> f32 {
+        self.clock.time()
+    }
+
+    /// Starts over at `time_offset` with frame 0, so buffer passes initialize again.
+    pub fn reset_time(&mut self) {
+        self.clock.set(self.time_offset);
+        self.frame = 0;
+    }
+
+    /// Renders the part `offset..offset + scale` of the uv range of an image of `size` pixels into
+    /// the whole target, used to render images tile by tile. `u.resolution` reports `size`, the
+    /// buffer passes keep their size.
+    pub fn set_viewport(
+        &mut self,
+        queue: &Queue,
+        size: WindowSize,
+        offset: [f32; 2],
+        scale: [f32; 2],
+    ) {
+        self.size = size;
+        let vertices = Vertex::rect(Vector2 { x: 0.0, y: 0.0 }, 2.0, 2.0, 0.0)
+            .into_iter()
+            .map(|v| Vertex {
+                pixel: Vector2 {
+                    x: offset[0] + v.pixel.x * scale[0],
+                    y: offset[1] + v.pixel.y * scale[1],
+                },
+                ..v
+            });
+        let mut vertex_bytes = vec![];
+        let mut vertex_bytes_writer = crevice::std430::Writer::new(&mut vertex_bytes);
+        vertex_bytes_writer.write_iter(vertices).unwrap();
+        queue.write_buffer(&self.vertices, 0, &vertex_bytes);
+    }
+
+    /// Adapts the size dependent resources to a new render target size.
+    pub fn resize(&mut self, device: &Device, size: WindowSize) {
+        self.size = size;
+        if let Some(passes) = &mut self.passes {
+            passes.resize(device, size);
+        }
+    }
+
+    /// Samples the clock for the next frame, called once before every render.
+    pub fn advance(&mut self) {
+        let time = self.time();
+        self.time_delta = if self.frame == 0 {
+            0.0
+        } else {
+            time - self.current_time
+        };
+        self.current_time = time;
+    }
+
+    /// Tracks the mouse in the Shadertoy `iMouse` convention, `position` in window pixels.
+    pub fn mouse_input(&mut self, position: (f32, f32), pressed: bool) {
+        let (x, y) = (position.0, self.size.height as f32 - position.1);
+        let was_pressed = self.mouse[2] > 0.0;
+        if pressed {
+            self.mouse[0] = x;
+            self.mouse[1] = y;
+            if !was_pressed {
+                self.mouse[2] = x;
+                self.mouse[3] = y;
+            }
+        } else if was_pressed {
+            self.mouse[2] = -self.mouse[2];
+            self.mouse[3] = -self.mouse[3];
+        }
+    }
+
+    /// Number of floats the fft storage buffer can hold, 0 if there is none.
+    fn fft_capacity(&self) -> usize {
+        self.buffers
+            .get(1)
+            .map(|b| b.size() as usize / std::mem::size_of::<f32>())
+            .unwrap_or(0)
+    }
+
+    pub fn uniforms(&self) -> Uniforms {
         let fft_lock = self.fft.lock().unwrap();
         let fft_value = fft_lock[0];
+        let fft_length = fft_lock.len().min(self.fft_capacity());
         drop(fft_lock);
-        if let Some(time_buffer) = self.buffers.get(0) {
-            write_to_buffer(
-                &[
-                    self.time_start.elapsed().as_secs_f32() + self.time_offset,
-                    fft_value,
-                    0.0,
-                    0.0,
-                ],
-                time_buffer,
-                queue,
-            );
+        Uniforms {
+            time: self.current_time,
+            fft: fft_value,
+            time_delta: self.time_delta,
+            frame: self.frame as f32,
+            resolution: Vector2 {
+                x: self.size.width as f32,
+                y: self.size.height as f32,
+            },
+            aspect: self.size.width as f32 / self.size.height as f32,
+            fft_length: fft_length as f32,
+            mouse: self.mouse.into(),
+            date: date().into(),
+            camera_position: self.camera.position.into(),
+            camera_fov: self.camera.fov,
+            camera_forward: self.camera.forward().into(),
+            camera_up: self.camera.up().into(),
+        }
+    }
+
+    pub fn update_buffers(&self, queue: &Queue, ui: &Option<UIElements>) {
+        if let Some(uniform_buffer) = self.buffers.get(Resource::Uniforms as usize) {
+            queue.write_buffer(uniform_buffer, 0, self.uniforms().as_std140().as_bytes());
         }
 
-        if let Some(ui_buffer) = self.buffers.get(1) {
+        if let Some(fft_buffer) = self.buffers.get(Resource::Fft as usize) {
+            let fft_lock = self.fft.lock().unwrap();
+            let fft_length = fft_lock.len().min(self.fft_capacity());
+            if fft_length > 0 {
+                write_to_buffer(&fft_lock[..fft_length], fft_buffer, queue);
+            }
+        }
+        if let Some(ui_buffer) = self.buffers.get(Resource::Sliders as usize) {
             if let Some(u) = ui {
                 let c = u.elements.iter().map(|u| u.value).collect::<Vec<_>>();
                 write_to_buffer(&c, ui_buffer, queue);
             }
         }
-        if let Some(eye_buffer) = self.buffers.get(2) {
+        if let Some(eye_buffer) = self.buffers.get(Resource::Eyes as usize) {
             let lock = self.eye_positions.lock().unwrap();
             let eye_buffer_content: Vec<f32> = lock.iter().cloned().flatten().collect();
             drop(lock);
@@ -209,18 +387,38 @@ impl MainDisplay {
     }
 }
 
+fn initial_uniforms() -> Uniforms {
+    Uniforms {
+        time: 0.0,
+        fft: 0.0,
+        time_delta: 0.0,
+        frame: 0.0,
+        resolution: Vector2 { x: 1.0, y: 1.0 },
+        aspect: 1.0,
+        fft_length: 0.0,
+        mouse: [0.0; 4].into(),
+        date: [0.0; 4].into(),
+        camera_position: [0.0; 3].into(),
+        camera_fov: 60.0,
+        camera_forward: [0.0, 0.0, 1.0].into(),
+        camera_up: [0.0, 1.0, 0.0].into(),
+    }
+}
+
+/// Buffers in binding order: uniforms, fft spectrum, sliders, eyes
 #[cfg(all(unix, not(target_family = "wasm")))]
-fn create_buffers(device: &Device, fft_value: f32) -> Vec<Buffer> {
-    let uniform_buffer = create_uniform_buffer("uniform", device, [0.0, fft_value, 0.0, 0.0]);
+fn create_buffers(device: &Device, fft_len: usize) -> Vec<Buffer> {
+    let uniform_buffer = create_uniforms_buffer("uniform", device, &initial_uniforms());
+    let fft_buffer = create_float_vec_buffer("fft", device, &vec![0.0; fft_len.max(1)]);
     let slider_buffer = create_float_vec_buffer("sliders", device, &[0.0; 10]);
     let eye_buffer = create_float_vec2_vec_buffer("eye", device, &[[-1.0, -1.0]]);
-    let buffers = vec![uniform_buffer, slider_buffer, eye_buffer];
+    let buffers = vec![uniform_buffer, fft_buffer, slider_buffer, eye_buffer];
     buffers
 }
 
 #[cfg(target_family = "wasm")]
-fn create_buffers(device: &Device, fft_value: f32) -> Vec<Buffer> {
-    let uniform_buffer = create_uniform_buffer("uniform", device, [0.0, fft_value, 0.0, 0.0]);
+fn create_buffers(device: &Device, _fft_len: usize) -> Vec<Buffer> {
+    let uniform_buffer = create_uniforms_buffer("uniform", device, &initial_uniforms());
     let buffers = vec![uniform_buffer];
     buffers
 }
@@ -311,7 +509,7 @@ impl UIElements {
             &render_pipeline_layout,
             &[Some(wgpu::ColorTargetState {
                 format,
-                blend: Some(wgpu::BlendState::REPLACE),
+                blend: render_pipeline::blend_state(format, wgpu::BlendState::REPLACE),
                 write_mask: wgpu::ColorWrites::ALL,
             })],
             &[Vertex::desc()],
@@ -417,6 +615,180 @@ impl UIElement {
     }
 }
 
+/// Lines and characters per line of the error message shown by the overlay.
+const ERROR_LINES: u32 = 6;
+const ERROR_COLUMNS: u32 = 200;
+
+/// Striped bar at the top of the window showing the first lines of a shader error.
+pub struct ErrorOverlay {
+    pub pipeline: RenderPipeline,
+    pub vertices: Buffer,
+    text: Texture,
+    parameters: Buffer,
+    bind_group: BindGroup,
+    /// message and scale the text texture and parameters were written for
+    shown: Option<(String, f32)>,
+}
+
+impl ErrorOverlay {
+    pub fn new(device: &Device, format: TextureFormat) -> Self {
+        let vertex_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("vertex_shader"),
+            source: wgpu::ShaderSource::Glsl {
+                shader: include_str!("ui.vert").into(),
+                stage: wgpu::naga::ShaderStage::Vertex,
+                defines: wgpu::naga::FastHashMap::default(),
+            },
+        });
+        let fragment_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("fragment_shader"),
+            source: wgpu::ShaderSource::Glsl {
+                shader: include_str!("error.frag").into(),
+                stage: wgpu::naga::ShaderStage::Fragment,
+                defines: wgpu::naga::FastHashMap::default(),
+            },
+        });
+        let text = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("error text"),
+            size: wgpu::Extent3d {
+                width: ERROR_COLUMNS * font::CELL[0],
+                height: ERROR_LINES * font::CELL[1],
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+        let parameters = create_uniform_buffer("error parameters", device, [1.0, 0.0, 0.0, 0.0]);
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("error bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform {},
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("error bind group"),
+            layout: &bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(
+                        &text.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: parameters.as_entire_binding(),
+                },
+            ],
+        });
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("error pipeline layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let pipeline = device.create_render_pipeline(&render_pipeline::render_pipeline_descriptor(
+            &vertex_shader,
+            &fragment_shader,
+            &render_pipeline_layout,
+            &[Some(wgpu::ColorTargetState {
+                format,
+                blend: render_pipeline::blend_state(format, wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            &[Vertex::desc()],
+        ));
+        // covers the window, the shader discards everything below the text
+        let vertices = Vertex::rect(Vector2 { x: 0.0, y: 0.0 }, 2.0, 2.0, 0.0);
+        let mut vertex_bytes = vec![];
+        let mut vertex_bytes_writer = crevice::std430::Writer::new(&mut vertex_bytes);
+        vertex_bytes_writer
+            .write_iter(vertices.iter().cloned())
+            .unwrap();
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("error vertex buffer"),
+            contents: &vertex_bytes[..],
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        Self {
+            pipeline,
+            vertices: vertex_buffer,
+            text,
+            parameters,
+            bind_group,
+            shown: None,
+        }
+    }
+
+    /// Draws the first lines of `message` into the text texture, scaled to stay readable in a
+    /// window of `size`. Only writes to the GPU when the message or the scale changed.
+    pub fn prepare(&mut self, queue: &Queue, message: &str, size: WindowSize) {
+        let scale = (size.height as f32 / 360.0).round().max(1.0);
+        if self
+            .shown
+            .as_ref()
+            .is_some_and(|(shown, shown_scale)| shown == message && *shown_scale == scale)
+        {
+            return;
+        }
+        let width = ERROR_COLUMNS * font::CELL[0];
+        queue.write_texture(
+            self.text.as_image_copy(),
+            &font::rasterize(message, ERROR_COLUMNS, ERROR_LINES),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(width),
+                rows_per_image: None,
+            },
+            self.text.size(),
+        );
+        let lines = message.lines().count().clamp(1, ERROR_LINES as usize);
+        write_to_buffer(&[scale, lines as f32], &self.parameters, queue);
+        self.shown = Some((message.to_string(), scale));
+    }
+
+    pub fn render<'a, 'b: 'a>(&'b self, render_pass: &mut RenderPass<'a>) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, self.vertices.slice(..));
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..6, 0..1);
+    }
+}
+
 fn create_bind_group(device: &Device, layout: &BindGroupLayout, buffers: &[&Buffer]) -> BindGroup {
     let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: None,
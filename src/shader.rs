@@ -0,0 +1,734 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::reflection::{reflect, Bindings};
+
+#[cfg(all(unix, not(target_family = "wasm")))]
+use std::time::{Duration, Instant, SystemTime};
+
+/// Names of the optional buffer passes, a pass is loaded from `<shader>.<name>.frag` next to the
+/// main shader. Passes have to be declared in order, loading stops at the first missing file.
+pub const BUFFER_NAMES: [&str; 4] = ["buffer_a", "buffer_b", "buffer_c", "buffer_d"];
+
+/// Declarations wrapped around Shadertoy sources, mapping the Shadertoy inputs to the uniforms.
+const SHADERTOY_PRELUDE: &str = include_str!("shadertoy.frag");
+const SHADERTOY_MAIN: &str = "
+void main() {
+    vec4 color = vec4(0.0, 0.0, 0.0, 1.0);
+    mainImage(color, shadertoy_uv * shadertoy.resolution);
+    shadertoy_color = vec4(color.rgb, 1.0);
+}
+";
+
+/// Fragment shader sources of one scene: the main shader that renders to the screen and the
+/// buffer passes that are rendered into offscreen textures before it.
+pub struct Shaders {
+    pub main: Fragment,
+    pub buffers: Vec<Fragment>,
+    pub options: ShaderOptions,
+    /// Files included by any of the passes.
+    pub includes: Vec<PathBuf>,
+}
+
+/// Command line settings applied to every loaded shader.
+#[derive(Clone, Debug, Default)]
+pub struct ShaderOptions {
+    /// Directories searched for `#include`d files after the directory of the including file.
+    pub include_dirs: Vec<PathBuf>,
+    /// `-D NAME=VALUE`, these override the defines file of the shader.
+    pub defines: Vec<(String, String)>,
+}
+
+/// Shading language of a fragment, WGSL for `.wgsl` files and GLSL otherwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Language {
+    Glsl,
+    Wgsl,
+}
+
+/// Source of a single fragment shader as it is handed to the compiler.
+pub struct Fragment {
+    /// file name used in error messages
+    pub name: String,
+    pub language: Language,
+    pub code: String,
+    /// Preprocessor defines, only supported by glsl.
+    pub defines: BTreeMap<String, String>,
+    /// File and line every line of `code` comes from, `None` for generated lines. Used to report
+    /// errors at the line of the file after includes and the Shadertoy prelude were inserted.
+    pub origins: Vec<Option<Origin>>,
+}
+
+/// Line of a source file, counted from 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Origin {
+    pub file: String,
+    pub line: usize,
+}
+
+impl Fragment {
+    /// Shadertoy sources (a `mainImage` function without `#version`) are wrapped with a prelude
+    /// providing `iTime`, `iResolution` etc. and a `main` calling `mainImage`.
+    pub fn new(name: &str, source: String) -> Fragment {
+        let origins = (1..=source.lines().count())
+            .map(|line| {
+                Some(Origin {
+                    file: name.to_string(),
+                    line,
+                })
+            })
+            .collect();
+        Fragment::with_origins(name, source, origins)
+    }
+
+    /// Reads a shader and resolves its includes, the included files are added to `includes`.
+    #[allow(dead_code)]
+    pub fn load(
+        path: &Path,
+        include_dirs: &[PathBuf],
+        includes: &mut Vec<PathBuf>,
+    ) -> std::io::Result<Fragment> {
+        let mut code = String::new();
+        let mut origins = Vec::new();
+        let mut included = Vec::new();
+        preprocess(path, include_dirs, &mut included, &mut code, &mut origins)?;
+        for file in included.into_iter().skip(1) {
+            if !includes.contains(&file) {
+                includes.push(file);
+            }
+        }
+        Ok(Fragment::with_origins(
+            &path.display().to_string(),
+            code,
+            origins,
+        ))
+    }
+
+    fn with_origins(name: &str, source: String, mut origins: Vec<Option<Origin>>) -> Fragment {
+        let language = if name.ends_with(".wgsl") {
+            Language::Wgsl
+        } else {
+            Language::Glsl
+        };
+        if language == Language::Glsl && is_shadertoy(&source) {
+            let mut prelude = vec![None; SHADERTOY_PRELUDE.lines().count()];
+            prelude.append(&mut origins);
+            Fragment {
+                name: name.to_string(),
+                language,
+                code: format!("{SHADERTOY_PRELUDE}{source}{SHADERTOY_MAIN}"),
+                defines: BTreeMap::new(),
+                origins: prelude,
+            }
+        } else {
+            Fragment {
+                name: name.to_string(),
+                language,
+                code: source,
+                defines: BTreeMap::new(),
+                origins,
+            }
+        }
+    }
+}
+
+/// Appends `path` to `code`, replacing `#include "file"` lines with the content of the file.
+/// Files are searched next to the including file and then in `include_dirs`. Every file is only
+/// inserted the first time it is included, as if it had an include guard. `included` holds the
+/// canonical paths of the files read so far.
+fn preprocess(
+    path: &Path,
+    include_dirs: &[PathBuf],
+    included: &mut Vec<PathBuf>,
+    code: &mut String,
+    origins: &mut Vec<Option<Origin>>,
+) -> std::io::Result<()> {
+    let canonical = path.canonicalize()?;
+    if included.contains(&canonical) {
+        return Ok(());
+    }
+    included.push(canonical);
+    let source = std::fs::read_to_string(path)?;
+    let name = path.display().to_string();
+    for (i, line) in source.lines().enumerate() {
+        let Some(include) = line.trim_start().strip_prefix("#include") else {
+            code.push_str(line);
+            code.push('\n');
+            origins.push(Some(Origin {
+                file: name.clone(),
+                line: i + 1,
+            }));
+            continue;
+        };
+        let error = |message: String| std::io::Error::other(format!("{name}:{}: {message}", i + 1));
+        let file = include
+            .trim()
+            .strip_prefix('"')
+            .and_then(|s| s.strip_suffix('"'))
+            .ok_or_else(|| error(format!("expected `#include \"file\"`, found `{line}`")))?;
+        let found = path
+            .parent()
+            .into_iter()
+            .chain(include_dirs.iter().map(PathBuf::as_path))
+            .map(|dir| dir.join(file))
+            .find(|candidate| candidate.is_file())
+            .ok_or_else(|| error(format!("can not find include `{file}`")))?;
+        preprocess(&found, include_dirs, included, code, origins)?;
+    }
+    Ok(())
+}
+
+/// `NAME=VALUE`, a `NAME` without value is defined as `1`.
+pub fn parse_define(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s.split_once('=').unwrap_or((s, "1"));
+    let name = name.trim();
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!("`{name}` is not a valid define name"));
+    }
+    Ok((name.to_string(), value.trim().to_string()))
+}
+
+/// Reads the defines file of a shader, one `NAME=VALUE` per line. Empty lines and lines
+/// starting with `#` or `//` are skipped, a missing file defines nothing.
+fn load_defines(path: &Path) -> std::io::Result<BTreeMap<String, String>> {
+    let path = defines_path(path);
+    let source = match std::fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => {
+            return Err(std::io::Error::new(
+                e.kind(),
+                format!("{}: {e}", path.display()),
+            ))
+        }
+    };
+    source
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#') && !line.starts_with("//"))
+        .map(|(i, line)| {
+            parse_define(line)
+                .map_err(|e| std::io::Error::other(format!("{}:{}: {e}", path.display(), i + 1)))
+        })
+        .collect()
+}
+
+/// Shadertoy sources declare `void mainImage(` and have no `#version` directive, comments are
+/// ignored.
+fn is_shadertoy(source: &str) -> bool {
+    let code = strip_comments(source);
+    let declares_main_image = code.match_indices("mainImage").any(|(i, name)| {
+        let before = &code[..i];
+        before.ends_with(char::is_whitespace)
+            && before
+                .trim_end()
+                .strip_suffix("void")
+                .is_some_and(|rest| !rest.ends_with(|c: char| c.is_alphanumeric() || c == '_'))
+            && code[i + name.len()..].trim_start().starts_with('(')
+    });
+    let has_version = code
+        .lines()
+        .any(|line| line.trim_start().starts_with("#version"));
+    declares_main_image && !has_version
+}
+
+/// `source` with `//` and `/* */` comments replaced by a space.
+fn strip_comments(source: &str) -> String {
+    let mut code = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find("//").into_iter().chain(rest.find("/*")).min() {
+        code.push_str(&rest[..start]);
+        code.push(' ');
+        let (end, close) = if rest[start..].starts_with("//") {
+            ("\n", 0)
+        } else {
+            ("*/", 2)
+        };
+        rest = match rest[start + 2..].find(end) {
+            Some(i) => &rest[start + 2 + i + close..],
+            None => "",
+        };
+    }
+    code.push_str(rest);
+    code
+}
+
+impl Shaders {
+    #[allow(dead_code)]
+    pub fn from_main(name: &str, main: &str) -> Shaders {
+        Shaders {
+            main: Fragment::new(name, main.to_string()),
+            buffers: Vec::new(),
+            options: ShaderOptions::default(),
+            includes: Vec::new(),
+        }
+    }
+
+    /// Loads the shader, its buffer passes and includes. All passes get the defines of
+    /// `<shader>.defines` next to the shader, overridden by the command line defines. wgsl has
+    /// no preprocessor, defines for a wgsl shader are an error.
+    #[allow(dead_code)]
+    pub fn load(path: &Path, options: &ShaderOptions) -> std::io::Result<Shaders> {
+        let mut defines = load_defines(path)?;
+        defines.extend(options.defines.iter().cloned());
+        if !defines.is_empty() && path.extension().is_some_and(|e| e == "wgsl") {
+            return Err(std::io::Error::other(
+                "-D defines and .defines files only apply to glsl shaders",
+            ));
+        }
+        let include_dirs = &options.include_dirs;
+        let mut includes = Vec::new();
+        let mut main = Fragment::load(path, include_dirs, &mut includes)?;
+        main.defines = defines.clone();
+        let mut buffers = Vec::new();
+        for name in BUFFER_NAMES {
+            let buffer_path = buffer_path(path, name);
+            if !buffer_path.exists() {
+                break;
+            }
+            let mut buffer = Fragment::load(&buffer_path, include_dirs, &mut includes)?;
+            buffer.defines = defines.clone();
+            buffers.push(buffer);
+        }
+        Ok(Shaders {
+            main,
+            buffers,
+            options: options.clone(),
+            includes,
+        })
+    }
+
+    /// Parses and validates the main shader and all buffer passes with naga, so mistakes are
+    /// reported with their location instead of a wgpu validation panic. Returns the resources
+    /// the shaders use in bind group 0, the buffer pass textures in group 1 are only checked.
+    pub fn check(&self) -> Result<Bindings, String> {
+        let mut bindings = Bindings::new();
+        let buffers = self.buffers.len();
+        let errors: Vec<_> = std::iter::once(&self.main)
+            .chain(&self.buffers)
+            .filter_map(|fragment| {
+                compile(fragment)
+                    .and_then(|(module, info)| {
+                        reflect(&fragment.name, &module, &info, buffers, &mut bindings)
+                    })
+                    .err()
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(bindings)
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+}
+
+/// Compiles a fragment shader in its language, see `compile_glsl` and `compile_wgsl`.
+pub fn compile(fragment: &Fragment) -> Result<(naga::Module, naga::valid::ModuleInfo), String> {
+    match fragment.language {
+        Language::Glsl => compile_glsl(fragment),
+        Language::Wgsl => compile_wgsl(fragment),
+    }
+}
+
+/// Compiles a glsl fragment shader and formats every error with file name, line, column and
+/// the offending line of source.
+pub fn compile_glsl(
+    fragment: &Fragment,
+) -> Result<(naga::Module, naga::valid::ModuleInfo), String> {
+    let options = naga::front::glsl::Options {
+        stage: naga::ShaderStage::Fragment,
+        defines: fragment
+            .defines
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect(),
+    };
+    let module = naga::front::glsl::Frontend::default()
+        .parse(&options, &fragment.code)
+        .map_err(|errors| {
+            errors
+                .iter()
+                .map(|e| format_error(fragment, e.meta, &e.kind.to_string()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })?;
+    validate(fragment, module)
+}
+
+/// Compiles a wgsl shader, which has to contain `@fragment fn main` with the same inputs,
+/// output and bindings as the glsl shaders.
+pub fn compile_wgsl(
+    fragment: &Fragment,
+) -> Result<(naga::Module, naga::valid::ModuleInfo), String> {
+    let name = &fragment.name;
+    let module = naga::front::wgsl::parse_str(&fragment.code).map_err(|e| {
+        let span = e.labels().next().map(|(span, _)| span).unwrap_or_default();
+        format_error(fragment, span, e.message())
+    })?;
+    if !module
+        .entry_points
+        .iter()
+        .any(|e| e.stage == naga::ShaderStage::Fragment && e.name == "main")
+    {
+        return Err(format!("error: no `@fragment fn main`\n --> {name}"));
+    }
+    validate(fragment, module)
+}
+
+fn validate(
+    fragment: &Fragment,
+    module: naga::Module,
+) -> Result<(naga::Module, naga::valid::ModuleInfo), String> {
+    let info = naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .map_err(|e| {
+        let span = e.spans().next().map(|(span, _)| *span).unwrap_or_default();
+        let mut message = e.as_inner().to_string();
+        let mut source = std::error::Error::source(e.as_inner());
+        while let Some(s) = source {
+            message.push_str(&format!(": {s}"));
+            source = s.source();
+        }
+        format_error(fragment, span, &message)
+    })?;
+    Ok((module, info))
+}
+
+fn format_error(fragment: &Fragment, span: naga::Span, message: &str) -> String {
+    let name = &fragment.name;
+    if !span.is_defined() {
+        return format!("error: {message}\n --> {name}");
+    }
+    let location = span.location(&fragment.code);
+    let line = fragment
+        .code
+        .lines()
+        .nth(location.line_number as usize - 1)
+        .unwrap_or_default();
+    let Some(Some(origin)) = fragment.origins.get(location.line_number as usize - 1) else {
+        return format!("error: {message}\n --> {name} (generated prelude)\n  | {line}");
+    };
+    let column = location.line_position as usize - 1;
+    let underline = (location.length as usize)
+        .min(line.len().saturating_sub(column))
+        .max(1);
+    let gutter = origin.line.to_string();
+    let padding = " ".repeat(gutter.len());
+    format!(
+        "error: {message}\n{padding}--> {}:{gutter}:{}\n{padding} |\n{gutter} | {line}\n{padding} | {}{}",
+        origin.file,
+        location.line_position,
+        " ".repeat(column),
+        "^".repeat(underline),
+    )
+}
+
+/// `shaders/trails.frag` -> `shaders/trails.defines`
+pub fn defines_path(path: &Path) -> PathBuf {
+    path.with_extension("defines")
+}
+
+/// `shaders/trails.frag` -> `shaders/trails.buffer_a.frag`, passes of a `.wgsl` shader are
+/// `.wgsl` files as well.
+pub fn buffer_path(path: &Path, name: &str) -> PathBuf {
+    let extension = match path.extension().and_then(|e| e.to_str()) {
+        Some("wgsl") => "wgsl",
+        _ => "frag",
+    };
+    path.with_extension(format!("{name}.{extension}"))
+}
+
+/// Polls the modification times of a shader, its buffer passes, defines file and includes.
+#[cfg(all(unix, not(target_family = "wasm")))]
+pub struct ShaderWatcher {
+    path: PathBuf,
+    options: ShaderOptions,
+    includes: Vec<PathBuf>,
+    modified: Vec<Option<SystemTime>>,
+    last_check: Instant,
+}
+
+#[cfg(all(unix, not(target_family = "wasm")))]
+impl ShaderWatcher {
+    pub fn new(path: &Path, shaders: &Shaders) -> ShaderWatcher {
+        let mut watcher = ShaderWatcher {
+            path: path.to_path_buf(),
+            options: shaders.options.clone(),
+            includes: Vec::new(),
+            modified: Vec::new(),
+            last_check: Instant::now(),
+        };
+        watcher.watch(shaders);
+        watcher
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn options(&self) -> &ShaderOptions {
+        &self.options
+    }
+
+    /// Watches the includes of freshly loaded shaders instead of the previous ones.
+    pub fn watch(&mut self, shaders: &Shaders) {
+        self.includes = shaders.includes.clone();
+        self.modified = self.modified_times();
+    }
+
+    fn modified_times(&self) -> Vec<Option<SystemTime>> {
+        watched_files(&self.path)
+            .iter()
+            .chain(&self.includes)
+            .map(|p| modified(p))
+            .collect()
+    }
+
+    /// Returns true if any of the files changed, appeared or disappeared since the last call.
+    /// The file system is checked at most every 250ms.
+    pub fn changed(&mut self) -> bool {
+        if self.last_check.elapsed() < Duration::from_millis(250) {
+            return false;
+        }
+        self.last_check = Instant::now();
+        let modified = self.modified_times();
+        let changed = modified != self.modified;
+        self.modified = modified;
+        changed
+    }
+}
+
+#[cfg(all(unix, not(target_family = "wasm")))]
+fn watched_files(path: &Path) -> Vec<PathBuf> {
+    [path.to_path_buf(), defines_path(path)]
+        .into_iter()
+        .chain(BUFFER_NAMES.iter().map(|name| buffer_path(path, name)))
+        .collect()
+}
+
+#[cfg(all(unix, not(target_family = "wasm")))]
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        compile_glsl, is_shadertoy, parse_define, Fragment, Language, ShaderOptions, Shaders,
+    };
+    use crate::{
+        reflection::{Binding, Resource},
+        util::TempDir,
+    };
+
+    #[test]
+    fn reports_location_of_errors() {
+        let source = "#version 450\nlayout (location = 0) out vec4 out_color;\nvoid main() {\n    out_color = vec4(undefined_value);\n}\n";
+        let error = compile_glsl(&Fragment::new("broken.frag", source.to_string())).unwrap_err();
+        assert!(error.contains("broken.frag:4:"), "{error}");
+        assert!(
+            error.contains("out_color = vec4(undefined_value);"),
+            "{error}"
+        );
+        assert!(error.contains('^'), "{error}");
+    }
+
+    #[test]
+    fn accepts_valid_shader() {
+        let source = "#version 450\nlayout (location = 0) out vec4 out_color;\nvoid main() {\n    out_color = vec4(1.0);\n}\n";
+        assert!(compile_glsl(&Fragment::new("valid.frag", source.to_string())).is_ok());
+    }
+
+    #[test]
+    fn wraps_shadertoy_sources() {
+        let source = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {\n    vec2 uv = fragCoord / iResolution.xy;\n    fragColor = vec4(uv, 0.5 + 0.5 * sin(iTime), float(iFrame));\n    fragColor += iMouse / vec4(undefined_value);\n}\n";
+        let error = compile_glsl(&Fragment::new("toy.frag", source.to_string())).unwrap_err();
+        assert!(error.contains("toy.frag:4:"), "{error}");
+        let valid = source.replace(
+            "iMouse / vec4(undefined_value)",
+            "iMouse * iDate.w * texture(iChannel2, uv)",
+        );
+        assert!(compile_glsl(&Fragment::new("toy.frag", valid)).is_ok());
+
+        assert!(is_shadertoy(
+            "// port\nvoid  mainImage (out vec4 c, in vec2 p) {}"
+        ));
+        let native = "layout (location = 0) out vec4 out_color;\n// unlike mainImage(), no wrapper\nvoid main() {\n    float mainImage = 1.0; /* void mainImage( */\n}\n";
+        assert!(!is_shadertoy(native));
+        assert!(!is_shadertoy(
+            "#version 450\nvoid mainImage(out vec4 c, in vec2 p) {}"
+        ));
+    }
+
+    #[test]
+    fn reflects_used_bindings() {
+        let source = "#version 450\nlayout (location = 0) out vec4 out_color;\nlayout (binding = 0) uniform UniformParameters { float time; } u;\nlayout (binding = 2) readonly buffer fftBuffer { float v[]; } fft;\nlayout (binding = 3) uniform texture2D channel1;\nlayout (binding = 4) uniform sampler channel1_sampler;\nlayout (binding = 5) readonly buffer SliderParameters { float v[]; } sliders;\nvoid main() {\n    out_color = vec4(u.time * fft.v[0]) * texture(sampler2D(channel1, channel1_sampler), vec2(0.5));\n}\n";
+        let bindings = Shaders::from_main("bindings.frag", source).check().unwrap();
+        assert_eq!(
+            bindings.into_iter().collect::<Vec<_>>(),
+            vec![
+                (0, Binding::Buffer(Resource::Uniforms)),
+                (2, Binding::Buffer(Resource::Fft)),
+                (3, Binding::Channel(1)),
+                (4, Binding::ChannelSampler(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_and_mistyped_bindings() {
+        let unknown = "#version 450\nlayout (location = 0) out vec4 out_color;\nlayout (binding = 1) readonly buffer Unknown { float v[]; } unknown;\nvoid main() {\n    out_color = vec4(unknown.v[0]);\n}\n";
+        let error = Shaders::from_main("unknown.frag", unknown)
+            .check()
+            .unwrap_err();
+        assert!(
+            error.contains("unknown resource `Unknown unknown` at binding 1"),
+            "{error}"
+        );
+        let mistyped = "#version 450\nlayout (location = 0) out vec4 out_color;\nlayout (binding = 1) uniform fftBuffer { vec4 v; } fft;\nvoid main() {\n    out_color = fft.v;\n}\n";
+        let error = Shaders::from_main("mistyped.frag", mistyped)
+            .check()
+            .unwrap_err();
+        assert!(
+            error.contains("has to be declared as `readonly buffer`"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn checks_buffer_pass_bindings() {
+        let main = "#version 450\nlayout (location = 0) out vec4 out_color;\nlayout (set = 1, binding = 0) uniform texture2D buffer_a;\nlayout (set = 1, binding = 4) uniform sampler buffer_sampler;\nvoid main() {\n    out_color = texture(sampler2D(buffer_a, buffer_sampler), vec2(0.5));\n}\n";
+        let with_buffer = |main: &str| Shaders {
+            main: Fragment::new("main.frag", main.to_string()),
+            buffers: vec![Fragment::new("main.buffer_a.frag", main.to_string())],
+            options: ShaderOptions::default(),
+            includes: Vec::new(),
+        };
+        assert!(with_buffer(main).check().is_ok());
+        let error = Shaders::from_main("main.frag", main).check().unwrap_err();
+        assert!(error.contains("set 1 binding 0 is not bound"), "{error}");
+
+        let unbound = main.replace("binding = 0", "binding = 1");
+        let error = with_buffer(&unbound).check().unwrap_err();
+        assert!(
+            error.contains("the 1 buffer passes use binding 0-0"),
+            "{error}"
+        );
+        let mistyped = main
+            .replace("texture2D", "texture3D")
+            .replace("sampler2D", "sampler3D")
+            .replace("vec2(0.5)", "vec3(0.5)");
+        let error = with_buffer(&mistyped).check().unwrap_err();
+        assert!(
+            error.contains("binding 0 is buffer_a and has to be declared as `uniform texture2D`"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn compiles_wgsl() {
+        let source = "struct UniformParameters { time: f32 }\n@group(0) @binding(0) var<uniform> u: UniformParameters;\n@group(0) @binding(4) var channel0: texture_2d<f32>;\n@group(0) @binding(5) var channel0_sampler: sampler;\n\n@fragment\nfn main(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {\n    return textureSample(channel0, channel0_sampler, uv) * u.time;\n}\n";
+        let shaders = Shaders::from_main("gradient.wgsl", source);
+        assert_eq!(shaders.main.language, Language::Wgsl);
+        assert_eq!(
+            shaders.check().unwrap().into_iter().collect::<Vec<_>>(),
+            vec![
+                (0, Binding::Buffer(Resource::Uniforms)),
+                (4, Binding::Channel(0)),
+                (5, Binding::ChannelSampler(0)),
+            ]
+        );
+        let broken = source.replace("u.time", "u.undefined_value");
+        let error = Shaders::from_main("gradient.wgsl", &broken)
+            .check()
+            .unwrap_err();
+        assert!(error.contains("gradient.wgsl:8:"), "{error}");
+        let renamed = source.replace("fn main", "fn fs_main");
+        let error = Shaders::from_main("gradient.wgsl", &renamed)
+            .check()
+            .unwrap_err();
+        assert!(error.contains("no `@fragment fn main`"), "{error}");
+    }
+
+    #[test]
+    fn resolves_includes() {
+        let dir = TempDir::new("includes");
+        let library = dir.join("library");
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        std::fs::create_dir_all(&library).unwrap();
+        std::fs::write(
+            dir.join("lib/common.glsl"),
+            "#include \"color.glsl\"\nfloat twice(float x) {\n    return 2.0 * x;\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            library.join("color.glsl"),
+            "vec3 gray(float v) {\n    return vec3(undefined_value);\n}\n",
+        )
+        .unwrap();
+        let path = dir.join("main.frag");
+        std::fs::write(&path, "#version 450\n#include \"lib/common.glsl\"\n#include \"color.glsl\"\nlayout (location = 0) out vec4 out_color;\nvoid main() {\n    out_color = vec4(gray(twice(0.25)), 1.0);\n}\n").unwrap();
+        let options = ShaderOptions {
+            include_dirs: vec![library],
+            defines: Vec::new(),
+        };
+        let shaders = Shaders::load(&path, &options).unwrap();
+        assert_eq!(shaders.includes.len(), 2);
+        assert_eq!(shaders.main.code.matches("vec3 gray").count(), 1);
+        let error = shaders.check().unwrap_err();
+        assert!(error.contains("color.glsl:2:"), "{error}");
+        assert!(Shaders::load(&path, &ShaderOptions::default()).is_err());
+    }
+
+    #[test]
+    fn applies_defines() {
+        assert_eq!(
+            parse_define("STEPS=64"),
+            Ok(("STEPS".to_string(), "64".to_string()))
+        );
+        assert_eq!(
+            parse_define("PREVIEW"),
+            Ok(("PREVIEW".to_string(), "1".to_string()))
+        );
+        assert!(parse_define("2D=1").is_err());
+
+        let dir = TempDir::new("defines");
+        let path = dir.join("steps.frag");
+        std::fs::write(&path, "#version 450\nlayout (location = 0) out vec4 out_color;\nvoid main() {\n#if STEPS > 100\n    out_color = vec4(undefined_value);\n#else\n    out_color = vec4(float(STEPS) * BRIGHTNESS);\n#endif\n}\n").unwrap();
+        std::fs::write(
+            dir.join("steps.defines"),
+            "# quality\nSTEPS=512\nBRIGHTNESS=0.5\n",
+        )
+        .unwrap();
+        let shaders = Shaders::load(&path, &ShaderOptions::default()).unwrap();
+        let error = shaders.check().unwrap_err();
+        assert!(error.contains("steps.frag:5:"), "{error}");
+        let options = ShaderOptions {
+            include_dirs: Vec::new(),
+            defines: vec![parse_define("STEPS=64").unwrap()],
+        };
+        let shaders = Shaders::load(&path, &options).unwrap();
+        assert_eq!(shaders.main.defines["BRIGHTNESS"], "0.5");
+        assert!(shaders.check().is_ok());
+
+        let wgsl = dir.join("gradient.wgsl");
+        std::fs::write(&wgsl, "").unwrap();
+        assert!(Shaders::load(&wgsl, &ShaderOptions::default()).is_ok());
+        let error = Shaders::load(&wgsl, &options).err().unwrap();
+        assert!(error.to_string().contains("only apply to glsl"), "{error}");
+        std::fs::write(dir.join("broken.frag"), "").unwrap();
+        std::fs::write(dir.join("broken.defines"), [0xff, 0xfe]).unwrap();
+        let error = Shaders::load(&dir.join("broken.frag"), &ShaderOptions::default())
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("broken.defines"), "{error}");
+    }
+}
@@ -0,0 +1,32 @@
+#version 450
+layout (location = 0) out vec4 shadertoy_color;
+layout (location = 0) in vec2 shadertoy_uv;
+layout (binding = 0) uniform UniformParameters {
+    float time;
+    float fft;
+    float time_delta;
+    float frame;
+    vec2 resolution;
+    float aspect;
+    float fft_length;
+    vec4 mouse;
+    vec4 date;
+} shadertoy;
+#define iTime shadertoy.time
+#define iTimeDelta shadertoy.time_delta
+#define iFrame int(shadertoy.frame)
+#define iResolution vec3(shadertoy.resolution, 1.0)
+#define iMouse shadertoy.mouse
+#define iDate shadertoy.date
+layout (binding = 4) uniform texture2D channel0;
+layout (binding = 5) uniform sampler channel0_sampler;
+layout (binding = 6) uniform texture2D channel1;
+layout (binding = 7) uniform sampler channel1_sampler;
+layout (binding = 8) uniform texture2D channel2;
+layout (binding = 9) uniform sampler channel2_sampler;
+layout (binding = 10) uniform texture2D channel3;
+layout (binding = 11) uniform sampler channel3_sampler;
+#define iChannel0 sampler2D(channel0, channel0_sampler)
+#define iChannel1 sampler2D(channel1, channel1_sampler)
+#define iChannel2 sampler2D(channel2, channel2_sampler)
+#define iChannel3 sampler2D(channel3, channel3_sampler)
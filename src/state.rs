@@ -1,27 +1,37 @@
-#[allow(dead_code)]
-use std::sync::{Arc, Mutex};
-
-use cfg_if::cfg_if;
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
 
-cfg_if! {
-if #[cfg(target_arch = "wasm32")] { } else {
-use image::{ImageBuffer, Rgba};
-}
-}
 use tokio::sync::oneshot::channel;
 use wgpu::{
     BufferAddress, BufferDescriptor, BufferUsages, ImageCopyBuffer, ImageCopyTexture,
-    ImageDataLayout, Instance, Origin3d, Surface, Texture, TextureFormat, TextureUsages,
-    TextureView,
+    ImageDataLayout, Instance, Origin3d, PresentMode, Surface, Texture, TextureFormat,
+    TextureUsages, TextureView,
 };
 use winit::keyboard::{Key, NamedKey};
 
-use crate::renderable::{MainDisplay, UIElements};
+use crate::{
+    camera::Camera,
+    channel::{ChannelImage, Channels},
+    playlist::{Crossfade, Playlist, PlaylistOptions},
+    profiler::{GpuProfiler, PassTimes, TimedPass},
+    renderable::{ErrorOverlay, MainDisplay, UIElements},
+    shader::Shaders,
+    tiles::Tile,
+    upscale::{RenderScale, Upscaler},
+    util::padded_bytes_per_row,
+};
 
 pub struct State<'a> {
     render_state: RenderState<'a>,
     main_display: MainDisplay,
     ui: Option<UIElements>,
+    error_overlay: ErrorOverlay,
+    shader_error: Option<String>,
+    upscaler: Upscaler,
+    playlist: Playlist,
+    crossfade: Crossfade,
 }
 
 enum SurfaceTypes<'a> {
@@ -34,10 +44,12 @@ struct RenderState<'a> {
     device: wgpu::Device,
     queue: wgpu::Queue,
     config: Option<wgpu::SurfaceConfiguration>,
-    format: TextureFormat, // size: winit::dpi::PhysicalSize<u32>,
+    format: TextureFormat,
+    size: WindowSize,
+    profiler: Option<GpuProfiler>,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct WindowSize {
     pub width: u32,
     pub height: u32,
@@ -50,6 +62,7 @@ impl<'a> RenderState<'a> {
         size: WindowSize,
         srgb: bool,
         format: Option<TextureFormat>,
+        present_mode: PresentMode,
     ) -> (RenderState<'a>, Option<TextureView>, Option<Texture>) {
         let adapter = instance
             .request_adapter(&wgpu::RequestAdapterOptions {
@@ -59,6 +72,8 @@ impl<'a> RenderState<'a> {
             })
             .await
             .unwrap();
+        // timestamp queries cost nothing until profiling is enabled
+        let required_features = adapter.features() & wgpu::Features::TIMESTAMP_QUERY;
         match surface {
             Some(surface) => {
                 let surface_caps = surface.get_capabilities(&adapter);
@@ -71,11 +86,18 @@ impl<'a> RenderState<'a> {
                     .find(|f| if srgb { f.is_srgb() } else { !f.is_srgb() })
                     .copied()
                     .unwrap_or(surface_caps.formats[0]);
+                // Fifo is the only mode every surface supports
+                let present_mode = if surface_caps.present_modes.contains(&present_mode) {
+                    present_mode
+                } else {
+                    log::warn!("{present_mode:?} is not supported, falling back to Fifo");
+                    PresentMode::Fifo
+                };
                 let (device, queue) = adapter
                     .request_device(
                         &wgpu::DeviceDescriptor {
                             label: Some("device"),
-                            required_features: wgpu::Features::empty(),
+                            required_features,
                             #[cfg(target_family = "wasm")]
                             required_limits: wgpu::Limits::downlevel_webgl2_defaults(),
                             #[cfg(all(unix, not(target_family = "wasm")))]
@@ -91,7 +113,7 @@ impl<'a> RenderState<'a> {
                     format: surface_format,
                     width: size.width,
                     height: size.height,
-                    present_mode: wgpu::PresentMode::Fifo,
+                    present_mode,
                     alpha_mode: surface_caps.alpha_modes[0],
                     view_formats: vec![],
                     desired_maximum_frame_latency: 2,
@@ -104,6 +126,8 @@ impl<'a> RenderState<'a> {
                         queue,
                         config: Some(config),
                         format: surface_format,
+                        size,
+                        profiler: None,
                     },
                     None,
                     None,
@@ -111,22 +135,29 @@ impl<'a> RenderState<'a> {
             }
             None => {
                 let (device, queue) = adapter
-                    .request_device(&Default::default(), None)
+                    .request_device(
+                        &wgpu::DeviceDescriptor {
+                            required_features,
+                            ..Default::default()
+                        },
+                        None,
+                    )
                     .await
                     .unwrap();
+                let format = format.unwrap();
                 let tecture_desc = wgpu::TextureDescriptor {
                     label: Some("output texture"),
                     size: wgpu::Extent3d {
-                        width: 1920,
-                        height: 1080,
+                        width: size.width,
+                        height: size.height,
                         depth_or_array_layers: 1,
                     },
                     mip_level_count: 1,
                     sample_count: 1,
                     dimension: wgpu::TextureDimension::D2,
-                    format: TextureFormat::Rgba8UnormSrgb,
+                    format,
                     usage: TextureUsages::COPY_SRC | TextureUsages::RENDER_ATTACHMENT,
-                    view_formats: &[TextureFormat::Rgba8UnormSrgb],
+                    view_formats: &[format],
                 };
 
                 let texture = device.create_texture(&tecture_desc);
@@ -137,7 +168,9 @@ impl<'a> RenderState<'a> {
                         device,
                         queue,
                         config: None,
-                        format: format.unwrap(),
+                        format,
+                        size,
+                        profiler: None,
                     },
                     Some(texture_view),
                     Some(texture),
@@ -145,23 +178,32 @@ impl<'a> RenderState<'a> {
             }
         }
     }
+    #[allow(clippy::too_many_arguments)]
     fn render(
         &mut self,
         main_display: &MainDisplay,
         ui: &Option<UIElements>,
-        file_render_view: Option<TextureView>,
-        file_render_texture: Option<Texture>,
-    ) -> Result<(), wgpu::SurfaceError> {
-        let (output, view) = match &self.surface {
+        error_overlay: Option<&ErrorOverlay>,
+        upscaler: &Upscaler,
+        crossfade: &Crossfade,
+        file_render_view: Option<&TextureView>,
+        file_render_texture: Option<&Texture>,
+    ) -> Result<Option<Vec<u8>>, wgpu::SurfaceError> {
+        let (output, window_view) = match &self.surface {
             SurfaceTypes::Window(s) => {
                 let o = s.get_current_texture()?;
                 let v = o
                     .texture
                     .create_view(&wgpu::TextureViewDescriptor::default());
-                (Some(o), v)
+                (Some(o), Some(v))
             }
-            SurfaceTypes::File() => (None, file_render_view.unwrap()),
+            SurfaceTypes::File() => (None, None),
         };
+        let view = window_view.as_ref().or(file_render_view).unwrap();
+        if let Some(profiler) = &mut self.profiler {
+            profiler.begin_frame(&self.device);
+        }
+        let profiler = self.profiler.as_ref();
         // .as_ref()
         // .map(|s| s.get_current_texture().unwrap());
         let mut encoder = self
@@ -169,11 +211,48 @@ impl<'a> RenderState<'a> {
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("Render Encoder"),
             });
+        main_display.render_buffers(&mut encoder);
+        if let Some((outgoing, fade_view)) = crossfade.outgoing() {
+            outgoing.render_buffers(&mut encoder);
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Crossfade Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: fade_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLUE),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            outgoing.render(&mut render_pass);
+        }
+        if let Some(scaled_view) = upscaler.view() {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Scaled Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: scaled_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLUE),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: profiler.and_then(|p| p.timestamp_writes(TimedPass::Main)),
+                occlusion_query_set: None,
+            });
+            main_display.render(&mut render_pass);
+            crossfade.render(&mut render_pass);
+        }
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color::BLUE),
@@ -181,26 +260,61 @@ impl<'a> RenderState<'a> {
                     },
                 })],
                 depth_stencil_attachment: None,
-                timestamp_writes: None,
+                timestamp_writes: profiler.and_then(|p| {
+                    p.timestamp_writes(if upscaler.view().is_some() {
+                        TimedPass::Upscale
+                    } else {
+                        TimedPass::Main
+                    })
+                }),
                 occlusion_query_set: None,
             });
 
-            main_display.render(&mut render_pass);
+            if upscaler.view().is_some() {
+                upscaler.render(&mut render_pass);
+            } else {
+                main_display.render(&mut render_pass);
+                crossfade.render(&mut render_pass);
+            }
+        }
+        if ui.as_ref().is_some_and(|u| !u.hidden) || error_overlay.is_some() {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("UI Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: profiler.and_then(|p| p.timestamp_writes(TimedPass::Ui)),
+                occlusion_query_set: None,
+            });
             if let Some(u) = ui {
                 u.render(&mut render_pass);
             }
+            if let Some(e) = error_overlay {
+                e.render(&mut render_pass);
+            }
+        }
+        if let Some(profiler) = profiler {
+            profiler.resolve(&mut encoder);
         }
 
         main_display.update_buffers(&self.queue, ui);
+        if let Some((outgoing, _)) = crossfade.outgoing() {
+            outgoing.update_buffers(&self.queue, ui);
+        }
 
         if let Some(u) = ui {
             u.update_buffers(&self.queue);
         }
-        let ob = match &self.surface {
-            SurfaceTypes::Window(_) => None,
-            SurfaceTypes::File() => {
-                let u32_size = std::mem::size_of::<u32>() as u32;
-                let output_buffer_size = (u32_size * 1920 * 1080) as BufferAddress;
+        let ob = match (&self.surface, file_render_texture) {
+            (SurfaceTypes::File(), Some(texture)) => {
+                let bytes_per_row = padded_bytes_per_row(self.size.width, self.bytes_per_pixel());
+                let output_buffer_size = (bytes_per_row * self.size.height) as BufferAddress;
                 let output_buffer_desc = BufferDescriptor {
                     label: Some("abc"),
                     size: output_buffer_size,
@@ -210,7 +324,7 @@ impl<'a> RenderState<'a> {
                 let output_buffer = self.device.create_buffer(&output_buffer_desc);
                 encoder.copy_texture_to_buffer(
                     ImageCopyTexture {
-                        texture: &file_render_texture.unwrap(),
+                        texture,
                         mip_level: 0,
                         origin: Origin3d::ZERO,
                         aspect: wgpu::TextureAspect::All,
@@ -219,44 +333,53 @@ impl<'a> RenderState<'a> {
                         buffer: &output_buffer,
                         layout: ImageDataLayout {
                             offset: 0,
-                            bytes_per_row: Some(u32_size * 1920),
-                            rows_per_image: Some(1080),
+                            bytes_per_row: Some(bytes_per_row),
+                            rows_per_image: Some(self.size.height),
                         },
                     },
                     wgpu::Extent3d {
-                        width: 1920,
-                        height: 1080,
+                        width: self.size.width,
+                        height: self.size.height,
                         depth_or_array_layers: 1,
                     },
                 );
                 Some(output_buffer)
-            } // None => {}
+            }
+            _ => None,
         };
         self.queue.submit(std::iter::once(encoder.finish()));
+        if let Some(profiler) = &mut self.profiler {
+            profiler.end_frame();
+        }
         match &self.surface {
             SurfaceTypes::Window(_s) => {
                 output.unwrap().present();
+                Ok(None)
             }
-            SurfaceTypes::File() => {
-                cfg_if! {
-                    if #[cfg(target_arch = "wasm32")] { }
-                    else {
-                        let buffer_slice = ob.as_ref().unwrap().slice(..);
-                        let (tx, rx) = channel();
-                        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-                            tx.send(result).unwrap();
-                        });
-                        self.device.poll(wgpu::MaintainBase::Wait);
-                        pollster::block_on(async { rx.await.unwrap().unwrap() });
-                        let data = buffer_slice.get_mapped_range();
-                        let buffer = ImageBuffer::<Rgba<u8>, _>::from_raw(1920, 1080, data).unwrap();
-                        buffer.save("screen.png").unwrap();
-                    }
-                }
-            }
+            SurfaceTypes::File() => Ok(ob.as_ref().map(|b| self.read_pixels(b))),
         }
-        // output.map(|o| o.present());
-        Ok(())
+    }
+
+    fn bytes_per_pixel(&self) -> u32 {
+        self.format.block_copy_size(None).unwrap()
+    }
+
+    /// Waits for the copy of a file render and returns its pixels without row padding.
+    fn read_pixels(&self, buffer: &wgpu::Buffer) -> Vec<u8> {
+        let buffer_slice = buffer.slice(..);
+        let (tx, rx) = channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            tx.send(result).unwrap();
+        });
+        self.device.poll(wgpu::MaintainBase::Wait);
+        pollster::block_on(async { rx.await.unwrap().unwrap() });
+        let data = buffer_slice.get_mapped_range();
+        let padded_row = padded_bytes_per_row(self.size.width, self.bytes_per_pixel()) as usize;
+        let row = (self.size.width * self.bytes_per_pixel()) as usize;
+        data.chunks(padded_row)
+            .flat_map(|r| &r[..row])
+            .copied()
+            .collect()
     }
 }
 
@@ -268,61 +391,332 @@ impl<'a> State<'a> {
         surface: Option<Surface<'a>>,
         format: Option<TextureFormat>,
         size: WindowSize,
-        fragment_shader_s: &str,
+        shaders: &[Shaders],
+        cameras: &[Camera],
         fft: &Arc<Mutex<Vec<f32>>>,
         time_offset: f32,
         eye_positions: Arc<Mutex<Vec<[f32; 2]>>>,
+        channels: &[Option<ChannelImage>],
         srgb: bool,
         pi: bool,
-    ) -> (Self, Option<TextureView>, Option<Texture>) {
+        present_mode: PresentMode,
+        playlist_options: PlaylistOptions,
+    ) -> Result<(Self, Option<TextureView>, Option<Texture>), String> {
         let (render_state, file_info, f) =
-            RenderState::new(instance, surface, size, srgb, format).await;
-        let main_display = MainDisplay::new(
-            fft.clone(),
-            eye_positions,
+            RenderState::new(instance, surface, size, srgb, format, present_mode).await;
+        let channels = Arc::new(Channels::new(
+            &render_state.device,
+            &render_state.queue,
+            channels,
+        ));
+        let mut displays = shaders
+            .iter()
+            .zip(cameras)
+            .map(|(shaders, camera)| {
+                let mut display = MainDisplay::new(
+                    fft.clone(),
+                    eye_positions.clone(),
+                    channels.clone(),
+                    &render_state.device,
+                    shaders,
+                    render_state.format,
+                    pi,
+                    time_offset,
+                    size,
+                )?;
+                display.camera = *camera;
+                Ok(display)
+            })
+            .collect::<Result<Vec<_>, String>>()?
+            .into_iter();
+        let main_display = displays.next().unwrap();
+        let playlist = Playlist::new(displays.collect(), playlist_options);
+        let crossfade = Crossfade::new(
             &render_state.device,
-            fragment_shader_s,
             render_state.format,
-            pi,
-            time_offset,
+            playlist_options.crossfade,
         );
         let ui = UIElements::new(&render_state.device, render_state.format);
+        let error_overlay = ErrorOverlay::new(&render_state.device, render_state.format);
+        let upscaler = Upscaler::new(
+            &render_state.device,
+            render_state.format,
+            RenderScale::new(1.0, None),
+        );
 
-        (
+        Ok((
             Self {
                 main_display,
                 ui,
+                error_overlay,
+                shader_error: None,
+                upscaler,
+                playlist,
+                crossfade,
                 render_state,
             },
             file_info,
             f,
-        )
+        ))
     }
+    /// Renders the next frame, for file renders the pixels of the output texture are returned if
+    /// `file_render_texture` is given.
     pub fn render(
         &mut self,
-        file_render_view: Option<TextureView>,
-        file_render_texture: Option<Texture>,
-    ) -> Result<(), wgpu::SurfaceError> {
-        self.render_state.render(
+        file_render_view: Option<&TextureView>,
+        file_render_texture: Option<&Texture>,
+    ) -> Result<Option<Vec<u8>>, wgpu::SurfaceError> {
+        let window_size = self.render_state.size;
+        let scaled_size = self.upscaler.scale.apply(window_size);
+        if scaled_size != self.main_display.size {
+            self.main_display
+                .resize(&self.render_state.device, scaled_size);
+        }
+        self.upscaler
+            .prepare(&self.render_state.device, scaled_size, window_size);
+        if self.playlist.due() {
+            self.switch_shader(self.playlist.offset(1));
+        }
+        if let Some(outgoing) = self.crossfade.finish(false) {
+            self.playlist.put_back(outgoing.index, outgoing.display);
+        }
+        self.crossfade.prepare(
+            &self.render_state.device,
+            &self.render_state.queue,
+            scaled_size,
+        );
+        self.main_display.advance();
+        if let Some(error) = &self.shader_error {
+            self.error_overlay
+                .prepare(&self.render_state.queue, error, window_size);
+        }
+        let pixels = self.render_state.render(
             &self.main_display,
             &self.ui,
+            self.shader_error.as_ref().map(|_| &self.error_overlay),
+            &self.upscaler,
+            &self.crossfade,
             file_render_view,
             file_render_texture,
-        )
+        )?;
+        self.main_display.frame += 1;
+        if let Some(outgoing) = &mut self.crossfade.outgoing {
+            outgoing.display.frame += 1;
+        }
+        Ok(pixels)
     }
 
+    /// Shows playlist entry `index`, the current shader fades out over it. A running crossfade
+    /// is cut short.
+    pub fn switch_shader(&mut self, index: usize) {
+        if index == self.playlist.current {
+            return;
+        }
+        if let Some(outgoing) = self.crossfade.finish(true) {
+            self.playlist.put_back(outgoing.index, outgoing.display);
+        }
+        let previous = self.playlist.current;
+        let mut incoming = self.playlist.take(index);
+        if incoming.size != self.main_display.size {
+            incoming.resize(&self.render_state.device, self.main_display.size);
+        }
+        let outgoing = std::mem::replace(&mut self.main_display, incoming);
+        if self.crossfade.duration > 0.0 {
+            self.crossfade.start(previous, outgoing);
+        } else {
+            self.playlist.put_back(previous, outgoing);
+        }
+        self.shader_error = None;
+        log::info!("playlist entry {index}");
+    }
+
+    /// Index of the shown playlist entry.
+    pub fn current_shader(&self) -> usize {
+        self.playlist.current
+    }
+
+    /// Renders the next frame of an image of `size` pixels tile by tile into the file texture,
+    /// all tiles share the time and frame number. `on_tile` receives the pixels of the whole
+    /// texture, tiles at the border only use the top left part of it.
+    pub fn render_tiles(
+        &mut self,
+        file_render_view: &TextureView,
+        file_render_texture: &Texture,
+        size: WindowSize,
+        tiles: &[Tile],
+        mut on_tile: impl FnMut(&Tile, Vec<u8>),
+    ) {
+        self.main_display.advance();
+        for tile in tiles {
+            let (offset, scale) = tile.viewport(size, self.render_state.size);
+            self.main_display
+                .set_viewport(&self.render_state.queue, size, offset, scale);
+            let pixels = self
+                .render_state
+                .render(
+                    &self.main_display,
+                    &self.ui,
+                    None,
+                    &self.upscaler,
+                    &self.crossfade,
+                    Some(file_render_view),
+                    Some(file_render_texture),
+                )
+                .unwrap()
+                .unwrap();
+            on_tile(tile, pixels);
+        }
+        self.main_display.frame += 1;
+    }
+
+    /// Starts the shown shader over at `--time` with frame 0.
+    #[allow(dead_code)]
+    pub fn reset_time(&mut self) {
+        self.main_display.reset_time();
+    }
+
+    /// Blocks until the GPU finished the submitted frames.
+    #[allow(dead_code)]
+    pub fn wait_idle(&self) {
+        self.render_state.device.poll(wgpu::Maintain::Wait);
+    }
+
+    /// Swaps in newly loaded shaders. If loading or compiling them failed the previous pipeline
+    /// keeps running and the error overlay is shown until a reload succeeds.
+    pub fn reload(&mut self, shaders: Result<Shaders, String>) -> Result<(), String> {
+        let device = &self.render_state.device;
+        let display = &mut self.main_display;
+        reload_with(&mut self.shader_error, shaders, |shaders| {
+            display.reload(device, shaders)
+        })
+    }
+
+    /// Times the render passes on the GPU if the adapter supports timestamp queries, `csv`
+    /// receives the times of every frame.
+    pub fn enable_profiling(&mut self, csv: Option<&Path>) -> std::io::Result<()> {
+        let render_state = &mut self.render_state;
+        if !render_state
+            .device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+        {
+            log::warn!(
+                "the adapter does not support timestamp queries, gpu times are not measured"
+            );
+            return Ok(());
+        }
+        render_state.profiler = Some(GpuProfiler::new(
+            &render_state.device,
+            &render_state.queue,
+            csv,
+        )?);
+        Ok(())
+    }
+
+    /// Average gpu time per pass since the last call, `None` without profiling.
+    pub fn gpu_times(&mut self) -> Option<PassTimes> {
+        self.render_state.profiler.as_mut().map(|p| p.averages())
+    }
+
+    pub fn set_render_scale(&mut self, scale: RenderScale) {
+        self.upscaler.scale = scale;
+    }
+
+    /// Feeds the measured frame rate to the automatic render scale.
+    pub fn report_fps(&mut self, fps: f32) {
+        if self.upscaler.scale.adjust(fps) {
+            log::info!("render scale {}", self.upscaler.scale.scale);
+        }
+    }
+
+    /// Replaces the wall clock of the main display with a fixed time, used for offline renders.
+    pub fn set_time(&mut self, time: f32) {
+        self.main_display.clock.pause_at(time);
+    }
+
+    /// Adapts the surface and the size dependent resources to the window size. Also used to
+    /// recover lost or outdated surfaces, the buffer passes keep their content if the size did not
+    /// change.
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        if new_size.width > 0 && new_size.height > 0 {
-            if let SurfaceTypes::Window(s) = &self.render_state.surface {
-                s.configure(
-                    &self.render_state.device,
-                    self.render_state.config.as_ref().unwrap(),
-                );
-            }
+        if new_size.width == 0 || new_size.height == 0 {
+            return;
+        }
+        let size = WindowSize {
+            width: new_size.width,
+            height: new_size.height,
+        };
+        if size != self.render_state.size {
+            self.render_state.size = size;
+            self.main_display
+                .resize(&self.render_state.device, self.upscaler.scale.apply(size));
+        }
+        if let (SurfaceTypes::Window(s), Some(config)) =
+            (&self.render_state.surface, &mut self.render_state.config)
+        {
+            config.width = size.width;
+            config.height = size.height;
+            s.configure(&self.render_state.device, config);
         }
     }
 
+    /// Camera of the shown shader.
+    pub fn camera(&self) -> Camera {
+        self.main_display.camera
+    }
+
+    /// Moves the camera by `movement` (right, up, forward) for `seconds`, see `Camera::fly`.
+    pub fn move_camera(&mut self, movement: [f32; 3], seconds: f32) {
+        self.main_display.camera.fly(movement, seconds);
+    }
+
+    /// Mouse drag by `delta` window pixels.
+    pub fn drag_camera(&mut self, delta: (f64, f64)) {
+        self.main_display
+            .camera
+            .drag((delta.0 as f32, delta.1 as f32));
+    }
+
+    /// Time transport keys, returns false for other keys.
+    fn report_transport(&mut self, key: &Key) -> bool {
+        let clock = &mut self.main_display.clock;
+        match key {
+            Key::Named(NamedKey::Space) => clock.toggle_pause(),
+            Key::Character(s) if s == "." => clock.step(1),
+            Key::Character(s) if s == "," => clock.step(-1),
+            Key::Character(s) if s == "]" => clock.seek(1.0),
+            Key::Character(s) if s == "[" => clock.seek(-1.0),
+            Key::Character(s) if s == "+" || s == "=" => clock.set_speed(clock.speed * 2.0),
+            Key::Character(s) if s == "-" => clock.set_speed(clock.speed / 2.0),
+            Key::Character(s) if s == "r" => clock.set_speed(-clock.speed),
+            Key::Named(NamedKey::Home) => self.main_display.reset_time(),
+            _ => return false,
+        }
+        true
+    }
+
     pub fn report_just_pressed(&mut self, key: Key) {
+        if self.report_transport(&key) {
+            let clock = &self.main_display.clock;
+            log::info!(
+                "time {:.3}s, speed {}{}",
+                clock.time(),
+                clock.speed,
+                if clock.paused { ", paused" } else { "" }
+            );
+        }
+        match &key {
+            Key::Named(NamedKey::ArrowRight | NamedKey::PageDown) => {
+                self.switch_shader(self.playlist.offset(1))
+            }
+            Key::Named(NamedKey::ArrowLeft | NamedKey::PageUp) => {
+                self.switch_shader(self.playlist.offset(-1))
+            }
+            Key::Character(s) if s == "c" => {
+                self.main_display.camera.toggle_mode();
+                log::info!("camera mode {:?}", self.main_display.camera.mode);
+            }
+            _ => (),
+        }
         if let Some(u) = &mut self.ui {
             match key {
                 Key::Character(s) if s == "m" => u.toggle_hidden(),
@@ -343,6 +737,15 @@ impl<'a> State<'a> {
         }
     }
 
+    /// `position` in window pixels, origin at the top left
+    pub fn report_mouse(&mut self, position: (f64, f64), pressed: bool) {
+        let scale = self.main_display.size.width as f32 / self.render_state.size.width as f32;
+        self.main_display.mouse_input(
+            (position.0 as f32 * scale, position.1 as f32 * scale),
+            pressed,
+        );
+    }
+
     #[allow(dead_code)]
     pub fn report_click(&mut self, position: (f32, f32)) {
         if let Some(u) = &mut self.ui {
@@ -350,3 +753,50 @@ impl<'a> State<'a> {
         }
     }
 }
+
+/// Applies `loaded` with `reload` and keeps the error of either step in `error`, cleared once a
+/// reload succeeds.
+fn reload_with(
+    error: &mut Option<String>,
+    loaded: Result<Shaders, String>,
+    reload: impl FnOnce(&Shaders) -> Result<(), String>,
+) -> Result<(), String> {
+    let result = loaded.and_then(|shaders| reload(&shaders));
+    *error = result.clone().err();
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::reload_with;
+    use crate::shader::{ShaderOptions, Shaders};
+
+    #[test]
+    fn failed_reloads_show_the_error() {
+        let mut error = None;
+        let missing = Shaders::load(Path::new("shaders/missing.frag"), &ShaderOptions::default())
+            .map_err(|e| e.to_string());
+        assert!(reload_with(&mut error, missing, |_| Ok(())).is_err());
+        assert!(error.is_some());
+
+        let broken = Shaders::from_main(
+            "broken.frag",
+            "#version 450\nlayout (location = 0) out vec4 out_color;\nvoid main() {\n    out_color = vec4(undefined_value);\n}\n",
+        );
+        let check = |shaders: &Shaders| shaders.check().map(|_| ());
+        assert!(reload_with(&mut error, Ok(broken), check).is_err());
+        assert!(
+            error.as_ref().unwrap().contains("broken.frag:4:"),
+            "{error:?}"
+        );
+
+        let valid = Shaders::from_main(
+            "valid.frag",
+            "#version 450\nlayout (location = 0) out vec4 out_color;\nvoid main() {\n    out_color = vec4(1.0);\n}\n",
+        );
+        assert!(reload_with(&mut error, Ok(valid), check).is_ok());
+        assert_eq!(error, None);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::state::WindowSize;
+
+/// Part of the output image in pixels, origin at the top left.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Splits an image into tiles of at most `tile_size` pixels per side, row by row from the top.
+pub fn tiles(size: WindowSize, tile_size: u32) -> Vec<Tile> {
+    (0..size.height)
+        .step_by(tile_size as usize)
+        .flat_map(|y| {
+            (0..size.width)
+                .step_by(tile_size as usize)
+                .map(move |x| Tile {
+                    x,
+                    y,
+                    width: tile_size.min(size.width - x),
+                    height: tile_size.min(size.height - y),
+                })
+        })
+        .collect()
+}
+
+impl Tile {
+    /// uv offset and scale of a render target of size `target` placed at the tile, so `uv` keeps
+    /// covering the full image. Tiles at the border are smaller than the target, the rest of the
+    /// target lies outside of the image and is cropped.
+    pub fn viewport(&self, size: WindowSize, target: WindowSize) -> ([f32; 2], [f32; 2]) {
+        let (width, height) = (size.width as f32, size.height as f32);
+        (
+            [
+                self.x as f32 / width,
+                1.0 - (self.y as f32 + target.height as f32) / height,
+            ],
+            [target.width as f32 / width, target.height as f32 / height],
+        )
+    }
+}
+
+/// The top left `width`×`height` pixels of an rgba image with rows of `stride` pixels.
+pub fn crop<T: Copy>(pixels: &[T], stride: u32, width: u32, height: u32) -> Vec<T> {
+    pixels
+        .chunks(stride as usize * 4)
+        .take(height as usize)
+        .flat_map(|row| &row[..width as usize * 4])
+        .copied()
+        .collect()
+}
+
+/// Copies the rgba pixels of `tile` into `image`, which is `image_width` pixels wide.
+pub fn stitch<T: Copy>(image: &mut [T], image_width: u32, tile: &Tile, pixels: &[T]) {
+    let row = tile.width as usize * 4;
+    for (i, src) in pixels.chunks(row).enumerate() {
+        let start = ((tile.y as usize + i) * image_width as usize + tile.x as usize) * 4;
+        image[start..start + row].copy_from_slice(src);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{crop, stitch, tiles, Tile};
+    use crate::state::WindowSize;
+
+    #[test]
+    fn tiles_cover_the_image() {
+        let size = WindowSize {
+            width: 5,
+            height: 3,
+        };
+        let tiles = tiles(size, 2);
+        assert_eq!(tiles.len(), 6);
+        assert_eq!(
+            tiles[5],
+            Tile {
+                x: 4,
+                y: 2,
+                width: 1,
+                height: 1
+            }
+        );
+        let target = WindowSize {
+            width: 2,
+            height: 2,
+        };
+        let (offset, scale) = tiles[0].viewport(size, target);
+        let expected = [0.0, 1.0 / 3.0, 0.4, 2.0 / 3.0];
+        for (a, b) in offset.iter().chain(&scale).zip(expected) {
+            assert!((a - b).abs() < 1e-6, "{offset:?} {scale:?}");
+        }
+
+        let mut image = vec![0; 5 * 3 * 4];
+        for (i, tile) in tiles.iter().enumerate() {
+            let target_pixels = vec![i as u8; 2 * 2 * 4];
+            stitch(
+                &mut image,
+                size.width,
+                tile,
+                &crop(&target_pixels, 2, tile.width, tile.height),
+            );
+        }
+        let red: Vec<_> = image.chunks(4).map(|p| p[0]).collect();
+        assert_eq!(red, vec![0, 0, 1, 1, 2, 0, 0, 1, 1, 2, 3, 3, 4, 4, 5]);
+    }
+}
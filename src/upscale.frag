@@ -0,0 +1,10 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 out_color;
+layout (binding = 0) uniform texture2D scaled;
+layout (binding = 1) uniform sampler scaled_sampler;
+
+void main() {
+    // texture rows start at the top, uv at the bottom
+    out_color = texture(sampler2D(scaled, scaled_sampler), vec2(uv.x, 1.0 - uv.y));
+}
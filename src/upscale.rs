@@ -0,0 +1,255 @@
+use mint::Vector2;
+use wgpu::{
+    util::DeviceExt, BindGroup, BindGroupLayout, BindGroupLayoutDescriptor, Buffer, Device,
+    RenderPass, RenderPipeline, Sampler, TextureFormat, TextureView,
+};
+
+use crate::{model::Vertex, render_pipeline, state::WindowSize};
+
+/// Smallest fraction of the window the shader is rendered at.
+pub const MIN_SCALE: f32 = 0.25;
+/// Frames between two automatic adjustments, the length of the `Fps` window.
+const ADJUST_INTERVAL: u32 = 32;
+
+/// Fraction of the window size the main shader is rendered at. With a target frame rate the
+/// fraction follows the measured frame rate, otherwise it stays fixed.
+#[derive(Clone, Copy, Debug)]
+pub struct RenderScale {
+    pub scale: f32,
+    pub target_fps: Option<f32>,
+    frames: u32,
+}
+
+impl RenderScale {
+    pub fn new(scale: f32, target_fps: Option<f32>) -> RenderScale {
+        RenderScale {
+            scale,
+            target_fps,
+            frames: 0,
+        }
+    }
+
+    /// Called once per frame. The rendered pixel count is proportional to `scale²`, so the scale
+    /// is changed by the square root of the frame rate ratio. With vsync the frame rate never
+    /// exceeds the target, so once it is reached the scale creeps up until it drops again.
+    /// Returns true if the scale changed.
+    pub fn adjust(&mut self, fps: f32) -> bool {
+        let Some(target_fps) = self.target_fps else {
+            return false;
+        };
+        self.frames += 1;
+        if self.frames < ADJUST_INTERVAL || !fps.is_finite() {
+            return false;
+        }
+        self.frames = 0;
+        let ratio = fps / target_fps;
+        let factor = if ratio < 0.95 {
+            ratio.sqrt().max(0.8)
+        } else if ratio > 0.98 {
+            1.05
+        } else {
+            1.0
+        };
+        let scale = (self.scale * factor).clamp(MIN_SCALE, 1.0);
+        let changed = (scale - self.scale).abs() > f32::EPSILON;
+        self.scale = scale;
+        changed
+    }
+
+    pub fn apply(&self, size: WindowSize) -> WindowSize {
+        WindowSize {
+            width: ((size.width as f32 * self.scale).round() as u32).max(1),
+            height: ((size.height as f32 * self.scale).round() as u32).max(1),
+        }
+    }
+}
+
+struct Target {
+    size: WindowSize,
+    view: TextureView,
+    bind_group: BindGroup,
+}
+
+/// Offscreen target for the main shader at a fraction of the window size, stretched over the
+/// window with a linear filter.
+pub struct Upscaler {
+    pub scale: RenderScale,
+    pipeline: RenderPipeline,
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    vertices: Buffer,
+    format: TextureFormat,
+    target: Option<Target>,
+}
+
+impl Upscaler {
+    pub fn new(device: &Device, format: TextureFormat, scale: RenderScale) -> Upscaler {
+        let vertex_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("vertex_shader"),
+            source: wgpu::ShaderSource::Glsl {
+                shader: include_str!("shader.vert").into(),
+                stage: wgpu::naga::ShaderStage::Vertex,
+                defines: wgpu::naga::FastHashMap::default(),
+            },
+        });
+        let fragment_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("fragment_shader"),
+            source: wgpu::ShaderSource::Glsl {
+                shader: include_str!("upscale.frag").into(),
+                stage: wgpu::naga::ShaderStage::Fragment,
+                defines: wgpu::naga::FastHashMap::default(),
+            },
+        });
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("upscale"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("upscale pipeline layout"),
+                bind_group_layouts: &[&layout],
+                push_constant_ranges: &[],
+            });
+        let pipeline = device.create_render_pipeline(&render_pipeline::render_pipeline_descriptor(
+            &vertex_shader,
+            &fragment_shader,
+            &render_pipeline_layout,
+            &[Some(wgpu::ColorTargetState {
+                format,
+                blend: render_pipeline::blend_state(format, wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            &[Vertex::desc()],
+        ));
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("upscale sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let vertices = Vertex::rect(Vector2 { x: 0.0, y: 0.0 }, 2.0, 2.0, 0.0);
+        let mut vertex_bytes = vec![];
+        let mut vertex_bytes_writer = crevice::std430::Writer::new(&mut vertex_bytes);
+        vertex_bytes_writer
+            .write_iter(vertices.iter().cloned())
+            .unwrap();
+        let vertices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("upscale vertex buffer"),
+            contents: &vertex_bytes[..],
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        Upscaler {
+            scale,
+            pipeline,
+            layout,
+            sampler,
+            vertices,
+            format,
+            target: None,
+        }
+    }
+
+    /// Makes sure the offscreen target has the given size, it is dropped at full scale.
+    pub fn prepare(&mut self, device: &Device, size: WindowSize, window_size: WindowSize) {
+        if size == window_size {
+            self.target = None;
+            return;
+        }
+        if self.target.as_ref().is_some_and(|t| t.size == size) {
+            return;
+        }
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("scaled texture"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&Default::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("upscale bind group"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        });
+        self.target = Some(Target {
+            size,
+            view,
+            bind_group,
+        });
+    }
+
+    /// The view the main shader renders into, `None` when it renders to the window directly.
+    pub fn view(&self) -> Option<&TextureView> {
+        self.target.as_ref().map(|t| &t.view)
+    }
+
+    pub fn render<'a, 'b: 'a>(&'b self, render_pass: &mut RenderPass<'a>) {
+        if let Some(target) = &self.target {
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_vertex_buffer(0, self.vertices.slice(..));
+            render_pass.set_bind_group(0, &target.bind_group, &[]);
+            render_pass.draw(0..6, 0..1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RenderScale, ADJUST_INTERVAL};
+    use crate::state::WindowSize;
+
+    #[test]
+    fn scale_follows_frame_rate() {
+        let mut scale = RenderScale::new(1.0, Some(60.0));
+        for _ in 0..ADJUST_INTERVAL - 1 {
+            assert!(!scale.adjust(15.0));
+        }
+        assert!(scale.adjust(15.0));
+        assert_eq!(scale.scale, 0.8);
+        for _ in 0..ADJUST_INTERVAL {
+            scale.adjust(60.0);
+        }
+        assert!((scale.scale - 0.84).abs() < 1e-6, "{}", scale.scale);
+        let size = scale.apply(WindowSize {
+            width: 1920,
+            height: 1080,
+        });
+        assert_eq!((size.width, size.height), (1613, 907));
+        let mut fixed = RenderScale::new(0.5, None);
+        assert!(!fixed.adjust(1.0));
+        assert_eq!(fixed.scale, 0.5);
+    }
+}
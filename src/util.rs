@@ -1,7 +1,8 @@
+use std::time::Duration;
 #[cfg(all(unix, not(target_family = "wasm")))]
-use std::time::Instant;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 #[cfg(target_family = "wasm")]
-use web_time::Instant;
+use web_time::{Instant, SystemTime, UNIX_EPOCH};
 
 pub struct Fps {
     buffer: [u64; 32],
@@ -26,8 +27,246 @@ impl Fps {
         self.next %= 32;
     }
 
+    /// Seconds between the last two presented frames.
+    pub fn frame_time(&self) -> f32 {
+        self.buffer[(self.next + 31) % 32] as f32 / 1000.0
+    }
+
     pub fn fps(&self) -> f32 {
         let time = self.buffer.iter().clone().sum::<u64>() as f32;
         32000.0 / time
     }
 }
+
+/// Schedules redraws at most `max_fps` times per second.
+pub struct FrameLimiter {
+    interval: Option<Duration>,
+    next: Instant,
+}
+
+impl FrameLimiter {
+    pub fn new(max_fps: Option<f32>) -> FrameLimiter {
+        FrameLimiter {
+            interval: max_fps.map(|fps| Duration::from_secs_f32(1.0 / fps)),
+            next: Instant::now(),
+        }
+    }
+
+    /// When the next frame is due, `None` without a cap. Late frames move the schedule instead of
+    /// being caught up with a burst of frames.
+    pub fn next_frame(&mut self) -> Option<Instant> {
+        let interval = self.interval?;
+        self.next = (self.next + interval).max(Instant::now());
+        Some(self.next)
+    }
+}
+
+/// wgpu requires the rows of a texture to buffer copy to be aligned to
+/// `COPY_BYTES_PER_ROW_ALIGNMENT`, this returns the padded row length.
+pub fn padded_bytes_per_row(width: u32, bytes_per_pixel: u32) -> u32 {
+    let unpadded = width * bytes_per_pixel;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    unpadded.div_ceil(align) * align
+}
+
+/// Averages `factor`×`factor` blocks of an srgb encoded rgba8 image in linear light, the resolve
+/// step of supersampling. `width` and `height` are the size of the input.
+pub fn downsample(pixels: &[u8], width: u32, height: u32, factor: u32) -> Vec<u8> {
+    let to_linear: Vec<f32> = (0..=255)
+        .map(|c| srgb_to_linear(c as f32 / 255.0))
+        .collect();
+    let to_srgb = |c: f32| (linear_to_srgb(c) * 255.0).round().clamp(0.0, 255.0) as u8;
+    let (out_width, out_height) = (width / factor, height / factor);
+    let samples = (factor * factor) as f32;
+    let mut out = Vec::with_capacity((out_width * out_height * 4) as usize);
+    for y in 0..out_height {
+        for x in 0..out_width {
+            let mut sum = [0.0f32; 4];
+            for sy in y * factor..(y + 1) * factor {
+                for sx in x * factor..(x + 1) * factor {
+                    let i = ((sy * width + sx) * 4) as usize;
+                    for c in 0..3 {
+                        sum[c] += to_linear[pixels[i + c] as usize];
+                    }
+                    sum[3] += pixels[i + 3] as f32 / 255.0;
+                }
+            }
+            out.extend(sum[..3].iter().map(|c| to_srgb(c / samples)));
+            out.push((sum[3] / samples * 255.0).round() as u8);
+        }
+    }
+    out
+}
+
+/// Averages `factor`×`factor` blocks of a linear rgba float image.
+pub fn downsample_linear(pixels: &[f32], width: u32, height: u32, factor: u32) -> Vec<f32> {
+    let (out_width, out_height) = (width / factor, height / factor);
+    let samples = (factor * factor) as f32;
+    let mut out = Vec::with_capacity((out_width * out_height * 4) as usize);
+    for y in 0..out_height {
+        for x in 0..out_width {
+            let mut sum = [0.0f32; 4];
+            for sy in y * factor..(y + 1) * factor {
+                for sx in x * factor..(x + 1) * factor {
+                    let i = ((sy * width + sx) * 4) as usize;
+                    for c in 0..4 {
+                        sum[c] += pixels[i + c];
+                    }
+                }
+            }
+            out.extend(sum.map(|c| c / samples));
+        }
+    }
+    out
+}
+
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Decodes an IEEE 754 half precision float, the channel type of `Rgba16Float` textures.
+pub fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 == 0 { 1.0 } else { -1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        31 if mantissa == 0.0 => f32::INFINITY,
+        31 => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+/// Current date as year, month (0-11), day (1-31) and seconds since midnight in UTC, matching
+/// Shadertoy's `iDate`.
+pub fn date() -> [f32; 4] {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    let days = (seconds / 86400.0).floor() as i64;
+    let (year, month, day) = civil_from_days(days);
+    [
+        year as f32,
+        (month - 1) as f32,
+        day as f32,
+        (seconds - days as f64 * 86400.0) as f32,
+    ]
+}
+
+/// Converts days since 1970-01-01 to (year, month 1-12, day 1-31),
+/// see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Directory for test files, unique per test and removed when dropped even if the test fails.
+#[cfg(test)]
+pub struct TempDir(std::path::PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    pub fn new(name: &str) -> TempDir {
+        static COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let count = COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("{name}_{}_{count}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TempDir {
+    type Target = std::path::Path;
+
+    fn deref(&self) -> &std::path::Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{civil_from_days, downsample, f16_to_f32, padded_bytes_per_row, FrameLimiter};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn days_to_date() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(59), (1970, 3, 1));
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+        assert_eq!(civil_from_days(20744), (2026, 10, 18));
+    }
+
+    #[test]
+    fn downsamples_in_linear_light() {
+        let pixels = [
+            [0, 0, 0, 255],
+            [255, 255, 255, 255],
+            [255, 255, 255, 255],
+            [0, 0, 0, 255],
+        ]
+        .concat();
+        // half of the light is 188 in srgb, not 128
+        assert_eq!(downsample(&pixels, 2, 2, 2), vec![188, 188, 188, 255]);
+        assert_eq!(downsample(&pixels, 2, 2, 1), pixels);
+    }
+
+    #[test]
+    fn rows_are_aligned() {
+        assert_eq!(padded_bytes_per_row(1920, 4), 1920 * 4);
+        assert_eq!(padded_bytes_per_row(1, 4), 256);
+        assert_eq!(padded_bytes_per_row(1080, 4), 4352);
+        assert_eq!(padded_bytes_per_row(64, 4), 256);
+        assert_eq!(padded_bytes_per_row(1080, 8), 8704);
+    }
+
+    #[test]
+    fn decodes_half_floats() {
+        assert_eq!(f16_to_f32(0x3c00), 1.0);
+        assert_eq!(f16_to_f32(0xc000), -2.0);
+        assert_eq!(f16_to_f32(0x7bff), 65504.0);
+        assert_eq!(f16_to_f32(0x0001), 2f32.powi(-24));
+        assert_eq!(f16_to_f32(0x7c00), f32::INFINITY);
+        assert!(f16_to_f32(0x7e00).is_nan());
+    }
+
+    #[test]
+    fn frames_are_spaced_by_the_cap() {
+        assert!(FrameLimiter::new(None).next_frame().is_none());
+        let start = Instant::now();
+        let mut limiter = FrameLimiter::new(Some(10.0));
+        let first = limiter.next_frame().unwrap();
+        let second = limiter.next_frame().unwrap();
+        assert!(first >= start + Duration::from_millis(99));
+        assert_eq!(second - first, Duration::from_secs_f32(0.1));
+    }
+}